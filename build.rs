@@ -1,21 +1,23 @@
 fn main() {
     if let Ok(p) = std::fs::read_dir("resources/levels").map(|d| d.flatten().map(|f| f.path())) {
-        let p = p
+        let mut p: Vec<_> = p
             .filter(|p| match p.extension() {
-                Some(s) => s.to_string_lossy().to_string() == "toml",
+                Some(s) => s == "toml",
                 _ => false,
             })
-            .map(|mut p| {
-                let path = format!("{}", p.to_string_lossy().to_string());
-                p.set_extension("");
-                let name = p
-                    .file_name()
-                    .expect("File name is not valid utf-8!")
-                    .to_string_lossy()
-                    .to_string();
+            .collect();
+        p.sort();
+        let p = p.into_iter().map(|mut p| {
+            let path = p.to_string_lossy().to_string();
+            p.set_extension("");
+            let name = p
+                .file_name()
+                .expect("File name is not valid utf-8!")
+                .to_string_lossy()
+                .to_string();
 
-                format!("levels.insert(\"{}\".to_string(), toml::from_str::<Level>(&include_str!(\"../{}\")).unwrap());", name, path.replace("\\", "/"))
-            });
+            format!("levels.insert(\"{}\".to_string(), toml::from_str::<Level>(include_str!(\"../{}\")).unwrap());", name, path.replace("\\", "/"))
+        });
 
         let mut v = vec![
             "/* AUTO GENERATED - DO NOT TOUCH */".to_string(),
@@ -31,7 +33,7 @@ fn main() {
 
         v.push("true".to_string());
         v.push("}".to_string());
-        std::fs::write("generated/default_levels.rs", format!("{}", v.join("\n"))).unwrap();
+        std::fs::write("generated/default_levels.rs", v.join("\n")).unwrap();
     } else {
         let mut v = vec![
             "use std::collections::HashMap;".to_string(),
@@ -41,6 +43,6 @@ fn main() {
 
         v.push("false".to_string());
         v.push("}".to_string());
-        std::fs::write("generated/default_levels.rs", format!("{}", v.join("\n"))).unwrap();
+        std::fs::write("generated/default_levels.rs", v.join("\n")).unwrap();
     }
 }
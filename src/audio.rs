@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+
+use ggez::audio;
+use ggez::audio::SoundSource;
+use ggez::timer;
+
+use crate::common::*;
+use crate::components::Player;
+use crate::events::GameEvent;
+
+const CROSSFADE_SECONDS: f32 = 1.5;
+
+/// Maps sound and music names to files in the resource directory, e.g. `jump = "/sounds/jump.ogg"`.
+#[derive(Deserialize, Debug, Default)]
+pub struct SoundManifest {
+    #[serde(default)]
+    pub sounds: HashMap<String, String>,
+    #[serde(default)]
+    pub music: HashMap<String, String>,
+}
+
+pub struct Music {
+    pub name: String,
+    pub source: audio::Source,
+}
+
+#[derive(Default)]
+pub struct Audio {
+    pub sounds: HashMap<String, audio::SoundData>,
+    pub music_files: HashMap<String, String>,
+    /// Tracks that are unknown or failed to play, they are only reported once.
+    pub failed_music: HashSet<String>,
+    pub music: Option<Music>,
+    pub fading_out: Option<Music>,
+    pub fade: f32,
+    /// Sounds and music requested during the tick, started in `update_audio`.
+    pub queued_sounds: Vec<String>,
+    pub queued_music: Option<Option<String>>,
+}
+
+impl Audio {
+    pub fn new(ctx: &mut Context) -> Self {
+        let manifest: SoundManifest = toml::from_str(
+            &std::fs::read_to_string("resources/sounds.toml")
+                .unwrap_or_else(|_| include_str!("../resources/sounds.toml").to_string()),
+        )
+        .unwrap_or_default();
+
+        let mut sounds = HashMap::new();
+        for (name, path) in manifest.sounds {
+            match audio::SoundData::new(ctx, &path) {
+                Ok(data) => {
+                    sounds.insert(name, data);
+                }
+                Err(e) => println!("failed to load sound {} ({}): {}", name, path, e),
+            }
+        }
+
+        Audio {
+            sounds,
+            music_files: manifest.music,
+            ..Default::default()
+        }
+    }
+}
+
+impl GameState {
+    fn sfx_volume(&self) -> f32 {
+        self.config.audio.master_volume * self.config.audio.sfx_volume
+    }
+
+    fn music_volume(&self) -> f32 {
+        self.config.audio.master_volume * self.config.audio.music_volume
+    }
+
+    pub fn play_sound(&mut self, name: &str) {
+        if self.audio.sounds.contains_key(name) {
+            self.audio.queued_sounds.push(name.to_string());
+        }
+    }
+
+    /// Crossfades to the given music track, or fades out if there is none.
+    pub fn play_music(&mut self, name: Option<&str>) {
+        self.audio.queued_music = Some(name.map(str::to_string));
+    }
+
+    fn start_sound(&mut self, ctx: &mut Context, name: &str) {
+        let data = match self.audio.sounds.get(name) {
+            Some(data) => data.clone(),
+            None => return,
+        };
+
+        let volume = self.sfx_volume();
+        let result = audio::Source::from_data(ctx, data).and_then(|mut source| {
+            source.set_volume(volume);
+            source.play_detached()
+        });
+
+        if let Err(e) = result {
+            println!("failed to play sound {}: {}", name, e);
+        }
+    }
+
+    fn start_music(&mut self, ctx: &mut Context, name: Option<&str>) {
+        let current = self.audio.music.as_ref().map(|music| music.name.as_str());
+        if current == name {
+            return;
+        }
+
+        if let Some(mut old) = self.audio.fading_out.take() {
+            old.source.stop();
+        }
+        self.audio.fading_out = self.audio.music.take();
+        self.audio.fade = 0.0;
+
+        let name = match name {
+            Some(name) if !self.audio.failed_music.contains(name) => name,
+            _ => return,
+        };
+
+        let path = match self.audio.music_files.get(name) {
+            Some(path) => path.clone(),
+            None => {
+                println!("unknown music track: {}", name);
+                self.audio.failed_music.insert(name.to_string());
+                return;
+            }
+        };
+
+        let result = audio::Source::new(ctx, &path).and_then(|mut source| {
+            source.set_repeat(true);
+            source.set_volume(0.0);
+            source.play()?;
+            Ok(source)
+        });
+
+        match result {
+            Ok(source) => {
+                self.audio.music = Some(Music {
+                    name: name.to_string(),
+                    source,
+                })
+            }
+            Err(e) => {
+                println!("failed to play music {} ({}): {}", name, path, e);
+                self.audio.failed_music.insert(name.to_string());
+            }
+        }
+    }
+
+    pub fn handle_audio_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Jumped(id) if self.world.get::<Player>(*id).is_ok() => {
+                self.play_sound("jump")
+            }
+            GameEvent::Landed(id) if self.world.get::<Player>(*id).is_ok() => {
+                self.play_sound("land")
+            }
+            GameEvent::PlayerDied => self.play_sound("death"),
+            GameEvent::CollectiblePicked { .. } => self.play_sound("collect"),
+            GameEvent::LevelChanged { from, to } => {
+                if from != to {
+                    self.play_sound("teleport");
+                }
+                let music = self.levels.get(to).and_then(|level| level.music.clone());
+                self.play_music(music.as_deref());
+            }
+            _ => {}
+        }
+    }
+
+    #[inline(always)]
+    pub fn update_audio(&mut self, ctx: &mut Context) -> GameResult<()> {
+        for name in std::mem::take(&mut self.audio.queued_sounds) {
+            self.start_sound(ctx, &name);
+        }
+        if let Some(music) = self.audio.queued_music.take() {
+            self.start_music(ctx, music.as_deref());
+        }
+
+        // runs once per frame, so the fade follows the real time rather than the tick rate
+        let elapsed = timer::duration_to_f64(timer::delta(ctx)) as f32;
+        self.audio.fade = (self.audio.fade + elapsed / CROSSFADE_SECONDS).min(1.0);
+
+        let volume = self.music_volume();
+        let fade = self.audio.fade;
+
+        if let Some(music) = &mut self.audio.music {
+            music.source.set_volume(volume * fade);
+        }
+
+        if fade >= 1.0 {
+            if let Some(mut old) = self.audio.fading_out.take() {
+                old.source.stop();
+            }
+        } else if let Some(old) = &mut self.audio.fading_out {
+            old.source.set_volume(volume * (1.0 - fade));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use ggez::graphics;
+use ggez::graphics::{DrawParam, Image, Mesh, Vertex};
+
+use crate::common::*;
+use crate::level::{Background, TextColor};
+
+#[inline(always)]
+fn to_rgba(color: &TextColor) -> [f32; 4] {
+    [
+        color.red as f32 / 255.0,
+        color.green as f32 / 255.0,
+        color.blue as f32 / 255.0,
+        1.0,
+    ]
+}
+
+/// First tile position so that repeated tiles cover the whole screen.
+#[inline(always)]
+fn first_tile(position: f32, size: f32) -> f32 {
+    position.rem_euclid(size) - size
+}
+
+impl GameState {
+    /// Loads the images of the current level's backgrounds, skipping the ones already tried.
+    fn load_backgrounds(&mut self, ctx: &mut Context) {
+        let images = self.levels[&self.current_level]
+            .backgrounds
+            .iter()
+            .filter_map(|background| background.image.clone())
+            .collect::<Vec<_>>();
+
+        for path in images {
+            if self.background_images.contains_key(&path) {
+                continue;
+            }
+
+            let image = Image::new(ctx, &path)
+                .map_err(|e| println!("failed to load background image ({}): {}", path, e))
+                .ok();
+            self.background_images.insert(path, image);
+        }
+    }
+
+    pub fn draw_backgrounds(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.load_backgrounds(ctx);
+
+        let level_center = self.levels[&self.current_level].size.height / 2.0;
+
+        for background in &self.levels[&self.current_level].backgrounds {
+            let x = background.offset_x - self.camera.center.x * background.parallax;
+            let y =
+                background.offset_y - (self.camera.center.y - level_center) * background.parallax;
+
+            if let Some(image) = background
+                .image
+                .as_ref()
+                .and_then(|path| self.background_images.get(path))
+                .and_then(Option::as_ref)
+            {
+                draw_image(ctx, background, image, x, y)?;
+            } else if !background.gradient.is_empty() {
+                draw_gradient(ctx, background, y)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn draw_image(
+    ctx: &mut Context,
+    background: &Background,
+    image: &Image,
+    x: f32,
+    y: f32,
+) -> GameResult<()> {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+
+    let (start_x, end_x) = if background.repeat_x {
+        (first_tile(x, width), WIDTH)
+    } else {
+        (x, x)
+    };
+    let (start_y, end_y) = if background.repeat_y {
+        (first_tile(y, height), HEIGHT)
+    } else {
+        (y, y)
+    };
+
+    let mut tile_y = start_y;
+    while tile_y <= end_y {
+        let mut tile_x = start_x;
+        while tile_x <= end_x {
+            graphics::draw(
+                ctx,
+                image,
+                DrawParam::default().dest(Point2::new(tile_x, tile_y)),
+            )?;
+            tile_x += width;
+        }
+        tile_y += height;
+    }
+
+    Ok(())
+}
+
+/// The gradient spans one screen height, the first and last colors extend to the screen edges.
+fn draw_gradient(ctx: &mut Context, background: &Background, y: f32) -> GameResult<()> {
+    let colors = &background.gradient;
+    let step = if colors.len() > 1 {
+        HEIGHT / (colors.len() - 1) as f32
+    } else {
+        0.0
+    };
+
+    let mut stops = vec![(y.min(0.0), to_rgba(&colors[0]))];
+    stops.extend(
+        colors
+            .iter()
+            .enumerate()
+            .map(|(i, color)| (y + i as f32 * step, to_rgba(color))),
+    );
+    let last = stops[stops.len() - 1];
+    stops.push((last.0.max(HEIGHT), last.1));
+
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    for (i, &(stop_y, color)) in stops.iter().enumerate() {
+        vertices.push(Vertex {
+            pos: [0.0, stop_y],
+            uv: [0.0, 0.0],
+            color,
+        });
+        vertices.push(Vertex {
+            pos: [WIDTH, stop_y],
+            uv: [0.0, 0.0],
+            color,
+        });
+
+        if i > 0 {
+            let top = (i as u32 - 1) * 2;
+            indices.extend_from_slice(&[top, top + 1, top + 3, top, top + 3, top + 2]);
+        }
+    }
+
+    let mesh = Mesh::from_raw(ctx, &vertices, &indices, None)?;
+    graphics::draw(ctx, &mesh, DrawParam::default())
+}
@@ -0,0 +1,559 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+
+use ggez::graphics::Rect;
+
+use crate::common::*;
+use crate::components::*;
+use crate::game_state::{load_config, load_levels, Controls};
+use crate::level::TriggerAction;
+use crate::scenes::Scene;
+
+/// The buttons held during a tick. A button counts as pressed on the first tick it's held.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Input {
+    #[serde(default)]
+    pub left: bool,
+    #[serde(default)]
+    pub right: bool,
+    #[serde(default)]
+    pub jump: bool,
+    #[serde(default)]
+    pub up: bool,
+    #[serde(default)]
+    pub down: bool,
+}
+
+impl Input {
+    pub const NONE: Input = Input {
+        left: false,
+        right: false,
+        jump: false,
+        up: false,
+        down: false,
+    };
+    pub const LEFT: Input = Input {
+        left: true,
+        ..Input::NONE
+    };
+    pub const RIGHT: Input = Input {
+        right: true,
+        ..Input::NONE
+    };
+    pub const JUMP: Input = Input {
+        jump: true,
+        ..Input::NONE
+    };
+    pub const LEFT_JUMP: Input = Input {
+        left: true,
+        jump: true,
+        ..Input::NONE
+    };
+    pub const RIGHT_JUMP: Input = Input {
+        right: true,
+        jump: true,
+        ..Input::NONE
+    };
+    pub const UP: Input = Input {
+        up: true,
+        ..Input::NONE
+    };
+    pub const DOWN: Input = Input {
+        down: true,
+        ..Input::NONE
+    };
+
+    const ALL: [Input; 8] = [
+        Input::NONE,
+        Input::LEFT,
+        Input::RIGHT,
+        Input::JUMP,
+        Input::LEFT_JUMP,
+        Input::RIGHT_JUMP,
+        Input::UP,
+        Input::DOWN,
+    ];
+
+    pub fn apply(self, controls: &mut Controls, previous: Input) {
+        controls.left_held = self.left;
+        controls.left_pressed = self.left && !previous.left;
+        controls.right_held = self.right;
+        controls.right_pressed = self.right && !previous.right;
+        controls.jump_held = self.jump;
+        controls.jump_pressed = self.jump && !previous.jump;
+        controls.up_held = self.up;
+        controls.up_pressed = self.up && !previous.up;
+        controls.down_held = self.down;
+        controls.down_pressed = self.down && !previous.down;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RouteStep {
+    pub ticks: usize,
+    #[serde(flatten)]
+    pub input: Input,
+}
+
+/// Inputs that lead from the start of a level into a teleporter, replayable from a fresh start.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Route {
+    pub level: String,
+    pub target: String,
+    pub ticks: usize,
+    #[serde(rename = "step")]
+    pub steps: Vec<RouteStep>,
+}
+
+impl Route {
+    fn push(&mut self, ticks: usize, input: Input) {
+        self.ticks += ticks;
+        match self.steps.last_mut() {
+            Some(step) if step.input == input => step.ticks += ticks,
+            _ => self.steps.push(RouteStep { ticks, input }),
+        }
+    }
+}
+
+/// The parts of an entity that change while the level is played.
+struct EntitySnapshot {
+    id: Entity,
+    position: Point2,
+    velocity: Option<Vector2>,
+    acceleration: Option<Vector2>,
+    gravity: Option<Vector2>,
+    grounded: Option<i32>,
+    ground: Option<Ground>,
+    health: Option<i32>,
+    invulnerable: Option<i32>,
+    patrol_target: Option<usize>,
+    air: Option<usize>,
+    swimming: bool,
+    climbing: bool,
+}
+
+struct Snapshot {
+    tick: usize,
+    entities: Vec<EntitySnapshot>,
+    keys: BTreeSet<String>,
+    flags: HashSet<String>,
+    trigger_overlaps: HashSet<(Entity, Entity)>,
+}
+
+struct Node {
+    snapshot: Snapshot,
+    parent: Option<usize>,
+    input: Input,
+    ticks: usize,
+}
+
+/// Quantized player state, nodes with the same key are only expanded once.
+#[derive(PartialEq, Eq, Hash)]
+struct StateKey {
+    position: (i32, i32),
+    velocity: (i32, i32),
+    grounded: bool,
+    climbing: bool,
+    jump_held: bool,
+    keys: BTreeSet<String>,
+    flags: BTreeSet<String>,
+}
+
+impl GameState {
+    fn snapshot(&self) -> Snapshot {
+        let entities = self
+            .world
+            .query::<(
+                &Position,
+                Option<&Velocity>,
+                Option<&Acceleration>,
+                Option<&Gravity>,
+                Option<&Grounded>,
+                Option<&Ground>,
+                Option<&Health>,
+                Option<&Invulnerable>,
+                Option<&EnemyAi>,
+                Option<&Air>,
+                Option<&Swimming>,
+                Option<&Climbing>,
+            )>()
+            .iter()
+            .map(
+                |(
+                    id,
+                    (
+                        position,
+                        velocity,
+                        acceleration,
+                        gravity,
+                        grounded,
+                        ground,
+                        health,
+                        invulnerable,
+                        ai,
+                        air,
+                        swimming,
+                        climbing,
+                    ),
+                )| {
+                    EntitySnapshot {
+                        id,
+                        position: position.0,
+                        velocity: velocity.map(|v| v.0),
+                        acceleration: acceleration.map(|a| a.0),
+                        gravity: gravity.map(|g| g.0),
+                        grounded: grounded.map(|g| g.0),
+                        ground: ground.copied(),
+                        health: health.map(|h| h.current),
+                        invulnerable: invulnerable.map(|i| i.0),
+                        patrol_target: ai.map(|ai| ai.target),
+                        air: air.map(|a| a.0),
+                        swimming: swimming.is_some(),
+                        climbing: climbing.is_some(),
+                    }
+                },
+            )
+            .collect();
+
+        Snapshot {
+            tick: self.tick,
+            entities,
+            keys: self.keys.clone(),
+            flags: self.flags.clone(),
+            trigger_overlaps: self.trigger_overlaps.clone(),
+        }
+    }
+
+    /// Entities despawned since the snapshot was taken stay despawned.
+    fn restore(&mut self, snapshot: &Snapshot) {
+        self.tick = snapshot.tick;
+        self.keys = snapshot.keys.clone();
+        self.flags = snapshot.flags.clone();
+        self.trigger_overlaps = snapshot.trigger_overlaps.clone();
+        self.events.drain();
+
+        for entity in &snapshot.entities {
+            if let Ok(mut q) = self.world.query_one::<(
+                &mut Position,
+                Option<&mut Velocity>,
+                Option<&mut Acceleration>,
+                Option<&mut Gravity>,
+                Option<&mut Health>,
+                Option<&mut EnemyAi>,
+                Option<&mut Air>,
+            )>(entity.id)
+            {
+                match q.get() {
+                    Some((position, velocity, acceleration, gravity, health, ai, air)) => {
+                        position.0 = entity.position;
+                        if let (Some(velocity), Some(value)) = (velocity, entity.velocity) {
+                            velocity.0 = value;
+                        }
+                        if let (Some(acceleration), Some(value)) =
+                            (acceleration, entity.acceleration)
+                        {
+                            acceleration.0 = value;
+                        }
+                        if let (Some(gravity), Some(value)) = (gravity, entity.gravity) {
+                            gravity.0 = value;
+                        }
+                        if let (Some(health), Some(value)) = (health, entity.health) {
+                            health.current = value;
+                        }
+                        if let (Some(ai), Some(value)) = (ai, entity.patrol_target) {
+                            ai.target = value;
+                        }
+                        if let (Some(air), Some(value)) = (air, entity.air) {
+                            air.0 = value;
+                        }
+                    }
+                    None => continue,
+                }
+            } else {
+                continue;
+            }
+
+            match entity.grounded {
+                Some(grounded) => {
+                    let _ = self.world.insert_one(entity.id, Grounded(grounded));
+                }
+                None => {
+                    let _ = self.world.remove_one::<Grounded>(entity.id);
+                }
+            }
+            match entity.ground {
+                Some(ground) => {
+                    let _ = self.world.insert_one(entity.id, ground);
+                }
+                None => {
+                    let _ = self.world.remove_one::<Ground>(entity.id);
+                }
+            }
+            if entity.swimming {
+                let _ = self.world.insert_one(entity.id, Swimming);
+            } else {
+                let _ = self.world.remove_one::<Swimming>(entity.id);
+            }
+            if entity.climbing {
+                let _ = self.world.insert_one(entity.id, Climbing);
+            } else {
+                let _ = self.world.remove_one::<Climbing>(entity.id);
+            }
+            match entity.invulnerable {
+                Some(ticks) => {
+                    let _ = self.world.insert_one(entity.id, Invulnerable(ticks));
+                }
+                None => {
+                    let _ = self.world.remove_one::<Invulnerable>(entity.id);
+                }
+            }
+        }
+    }
+
+    fn state_key(&self, jump_held: bool) -> Option<StateKey> {
+        let bot = &self.config.bot;
+        self.world
+            .query::<(
+                &Position,
+                &Velocity,
+                Option<&Grounded>,
+                Option<&Climbing>,
+                &Player,
+            )>()
+            .iter()
+            .map(
+                |(_id, (position, velocity, grounded, climbing, _))| StateKey {
+                    position: (
+                        (position.0.x / bot.position_step).round() as i32,
+                        (position.0.y / bot.position_step).round() as i32,
+                    ),
+                    velocity: (
+                        (velocity.0.x / bot.velocity_step).round() as i32,
+                        (velocity.0.y / bot.velocity_step).round() as i32,
+                    ),
+                    grounded: grounded.is_some_and(|g| g.0 > 0),
+                    climbing: climbing.is_some(),
+                    jump_held,
+                    keys: self.keys.clone(),
+                    flags: self.flags.iter().cloned().collect(),
+                },
+            )
+            .next()
+    }
+
+    /// Lower bound of the ticks needed to reach the closest teleporter to `target`.
+    fn remaining_ticks(&self, targets: &[Rect]) -> f32 {
+        let physics = &self.config.physics;
+        let max_speed = physics
+            .max_horizontal_velocity
+            .hypot(physics.max_vertical_velocity);
+
+        self.world
+            .query::<(&Position, &BoundingBox, &Player)>()
+            .iter()
+            .map(|(_id, (position, BoundingBox(bbox), _))| {
+                let mut bbox = *bbox;
+                bbox.translate(Vector2::new(position.0.x, position.0.y));
+                targets
+                    .iter()
+                    .map(|target| {
+                        let dx = (target.left() - bbox.right())
+                            .max(bbox.left() - target.right())
+                            .max(0.0);
+                        let dy = (target.top() - bbox.bottom())
+                            .max(bbox.top() - target.bottom())
+                            .max(0.0);
+                        dx.hypot(dy)
+                    })
+                    .fold(f32::INFINITY, f32::min)
+            })
+            .next()
+            .unwrap_or(f32::INFINITY)
+            / max_speed
+    }
+
+    fn teleporters_to(&self, target: &str) -> Vec<Rect> {
+        self.world
+            .query::<(&Trigger, &BoundingBox)>()
+            .iter()
+            .filter(|(_id, (trigger, _))| {
+                trigger.on_enter.iter().any(|action| match action {
+                    TriggerAction::Teleport { level } => level == target,
+                    _ => false,
+                })
+            })
+            .map(|(_id, (_, BoundingBox(bbox)))| *bbox)
+            .collect()
+    }
+
+    /// A fresh start of the current level with the same keys and flags, without particles, for
+    /// the bot to step through without affecting the game.
+    fn search_state(&self) -> GameResult<GameState> {
+        let mut config = self.config.clone();
+        config.particles.enabled = false;
+
+        let mut state = GameState::from_levels(config, HashMap::new());
+        state.levels = self.levels.clone();
+        state.keys = self.keys.clone();
+        state.flags = self.flags.clone();
+        state.scenes = vec![Scene::Playing];
+        state.change_level(self.current_level.clone())?;
+        state.process_events()?;
+        Ok(state)
+    }
+
+    /// Searches for inputs that take the player from the start of the current level into a
+    /// teleporter to `target`, stepping a separate copy of the level so the game isn't affected.
+    ///
+    /// The search weighs the distance to the target by `BotConfig::greed`, above 1 it finds
+    /// routes quickly but they aren't necessarily the fastest. Only the movement state of
+    /// entities is restored between branches, so entities despawned on one branch, like picked up
+    /// collectibles or opened doors, are gone on the others too. The route found is replayed from
+    /// a fresh start and dropped if that doesn't reach the target.
+    pub fn find_route(&self, target: &str) -> Option<Route> {
+        let route = self.search_state().ok()?.search(target)?;
+        if self.search_state().ok()?.replays(&route) {
+            Some(route)
+        } else {
+            None
+        }
+    }
+
+    fn replays(&mut self, route: &Route) -> bool {
+        let mut previous = Input::NONE;
+        for step in &route.steps {
+            for _ in 0..step.ticks {
+                step.input.apply(&mut self.controls, previous);
+                previous = step.input;
+                match self.advance() {
+                    Ok(None) => {}
+                    Ok(Some(level)) => return level == route.target,
+                    Err(_) => return false,
+                }
+            }
+        }
+        false
+    }
+
+    fn search(&mut self, target: &str) -> Option<Route> {
+        let targets = self.teleporters_to(target);
+        if targets.is_empty() {
+            return None;
+        }
+
+        let action_ticks = self.config.bot.action_ticks.max(1);
+        let greed = self.config.bot.greed;
+
+        let mut nodes = vec![Node {
+            snapshot: self.snapshot(),
+            parent: None,
+            input: Input::NONE,
+            ticks: 0,
+        }];
+        let mut open = BinaryHeap::new();
+        open.push(Reverse((0u64, 0usize)));
+        let mut visited = HashSet::new();
+        let mut found = None;
+
+        'search: while let Some(Reverse((_, index))) = open.pop() {
+            if nodes.len() >= self.config.bot.max_nodes {
+                break;
+            }
+
+            for &input in Input::ALL.iter() {
+                self.restore(&nodes[index].snapshot);
+                let mut previous = nodes[index].input;
+                let mut ticks = nodes[index].ticks;
+                let mut alive = true;
+
+                for _ in 0..action_ticks {
+                    input.apply(&mut self.controls, previous);
+                    previous = input;
+                    ticks += 1;
+                    match self.advance() {
+                        Ok(None) => {}
+                        Ok(Some(level)) if level == target => {
+                            nodes.push(Node {
+                                snapshot: self.snapshot(),
+                                parent: Some(index),
+                                input,
+                                ticks,
+                            });
+                            found = Some(nodes.len() - 1);
+                            break 'search;
+                        }
+                        _ => {
+                            alive = false;
+                            break;
+                        }
+                    }
+                }
+
+                if !alive {
+                    continue;
+                }
+                match self.state_key(input.jump) {
+                    Some(key) if !visited.contains(&key) => {
+                        visited.insert(key);
+                    }
+                    _ => continue,
+                }
+
+                let estimate = ticks as f32 + greed * self.remaining_ticks(&targets);
+                nodes.push(Node {
+                    snapshot: self.snapshot(),
+                    parent: Some(index),
+                    input,
+                    ticks,
+                });
+                open.push(Reverse(((estimate * 16.0) as u64, nodes.len() - 1)));
+            }
+        }
+
+        found.map(|mut index| {
+            let mut inputs = vec![];
+            while let Some(parent) = nodes[index].parent {
+                inputs.push((nodes[index].ticks - nodes[parent].ticks, nodes[index].input));
+                index = parent;
+            }
+
+            let mut route = Route {
+                level: self.current_level.clone(),
+                target: target.to_string(),
+                ticks: 0,
+                steps: vec![],
+            };
+            for (ticks, input) in inputs.into_iter().rev() {
+                route.push(ticks, input);
+            }
+            route
+        })
+    }
+}
+
+/// Finds a route through `level` into its teleporter to `target` and prints it as a replay.
+pub fn solve(level: &str, target: &str) -> GameResult {
+    let mut state = GameState::from_levels(load_config(), load_levels());
+    if !state.levels.contains_key(level) {
+        println!("unknown level {}", level);
+        return Ok(());
+    }
+    state.change_level(level.to_string())?;
+    state.process_events()?;
+
+    match state.find_route(target) {
+        Some(route) => {
+            println!(
+                "found a route from {} to {} in {} ticks",
+                level, target, route.ticks
+            );
+            match toml::to_string(&route) {
+                Ok(data) => println!("{}", data),
+                Err(e) => println!("failed to serialize route: {}", e),
+            }
+        }
+        None => println!("no route from {} to {} found", level, target),
+    }
+
+    Ok(())
+}
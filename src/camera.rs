@@ -3,7 +3,7 @@ use crate::components::*;
 
 impl GameState {
     #[inline(always)]
-    pub fn move_camera(&mut self, _ctx: &mut Context) -> GameResult<()> {
+    pub fn move_camera(&mut self) -> GameResult<()> {
         for (_id, (position, _)) in &mut self.world.query::<(&Position, &Player)>() {
             let difference = self.camera.center.x - position.0.x;
             if difference.abs() > self.config.camera.deadzone {
@@ -1,6 +1,7 @@
-use ggez::graphics::Rect;
+use ggez::graphics::{Color, Rect};
 
 use crate::common::*;
+use crate::level::{CollectibleKind, Layer, Requirement, TriggerAction};
 use crate::physics::*;
 
 pub struct Velocity(pub Vector2);
@@ -59,11 +60,223 @@ pub struct BoundingBox(pub Rect);
 
 pub struct Grounded(pub i32);
 
-pub struct Death;
+/// How a surface affects the entities standing on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    /// Multiplier of the friction in the physics config.
+    pub friction: f32,
+    /// Share of the speed an entity keeps when it bounces off, nothing bounces at 0.
+    pub restitution: f32,
+    /// Multiplier of the maximum horizontal velocity.
+    pub speed: f32,
+    /// Velocity of the surface itself, along its direction.
+    pub conveyor: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            friction: 1.0,
+            restitution: 0.0,
+            speed: 1.0,
+            conveyor: 0.0,
+        }
+    }
+}
+
+/// The surface the entity last stood on.
+#[derive(Clone, Copy, Debug)]
+pub struct Ground {
+    /// Points away from the surface.
+    pub normal: Vector2,
+    pub material: Material,
+}
+
+impl Default for Ground {
+    fn default() -> Self {
+        Ground {
+            normal: Vector2::new(0.0, -1.0),
+            material: Material::default(),
+        }
+    }
+}
+
+/// A ramp whose surface is the line between two points, solid below it down to the bounding box.
+#[derive(Clone, Copy, Debug)]
+pub struct Slope {
+    pub start: Point2,
+    pub end: Point2,
+}
+
+impl Slope {
+    /// Height of the surface at `x`, clamped to the ends of the slope.
+    pub fn surface_at(&self, x: f32) -> f32 {
+        let t = ((x - self.start.x) / (self.end.x - self.start.x)).clamp(0.0, 1.0);
+        self.start.y + (self.end.y - self.start.y) * t
+    }
+
+    /// Change in height per horizontal pixel.
+    pub fn gradient(&self) -> f32 {
+        (self.end.y - self.start.y) / (self.end.x - self.start.x)
+    }
+
+    pub fn normal(&self) -> Vector2 {
+        let tangent = (self.end - self.start).normalize_safe();
+        Vector2::new(tangent.y, -tangent.x)
+    }
+}
+
+pub struct Damage(pub i32);
+
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+}
+
+pub struct Invulnerable(pub i32);
+
+/// A region entities swim in.
+pub struct Water;
+
+/// The entity's center is in water.
+pub struct Swimming;
+
+/// Ticks left before the entity drowns while its head is under water.
+pub struct Air(pub usize);
+
+/// A region that can be climbed, and stood on at its top.
+pub struct Ladder;
+
+/// The entity holds on to a ladder instead of falling.
+pub struct Climbing;
+
+pub struct EnemyAi {
+    pub patrol: Vec<f32>,
+    pub target: usize,
+    pub direction: f32,
+    pub chase_radius: f32,
+}
 
 pub struct LevelId(pub DefaultAtom);
 
-pub struct TeleportTo(pub String);
+/// Continuously emits particles while the entity runs on the ground.
+#[derive(Clone, Debug)]
+pub struct ParticleEmitter {
+    pub rate: f32,
+    pub lifetime: u32,
+    pub speed: f32,
+    pub angle: f32,
+    pub spread: f32,
+    pub gravity: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub start_size: f32,
+    pub end_size: f32,
+    pub accumulator: f32,
+}
+
+/// Drawn as a filled rectangle, the mesh is built the first time the entity is drawn.
+pub struct Shape {
+    pub rect: Rect,
+    pub color: Color,
+}
+
+/// Like `Shape`, for outlines that aren't rectangles. Points are relative to the bounding box.
+pub struct Polygon {
+    pub points: Vec<Point2>,
+    pub color: Color,
+}
+
+/// Lets level scripts refer to an entity.
+pub struct Name(pub String);
+
+/// A region that runs actions when entities enter, stay in or leave it.
+pub struct Trigger {
+    pub on_enter: Vec<TriggerAction>,
+    pub on_stay: Vec<TriggerAction>,
+    pub on_exit: Vec<TriggerAction>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerMask(pub u32);
+
+impl LayerMask {
+    pub const ALL: LayerMask = LayerMask(!0);
+
+    pub fn of(layers: &[Layer]) -> Self {
+        LayerMask(
+            layers
+                .iter()
+                .fold(0, |mask, &layer| mask | 1 << layer as u32),
+        )
+    }
+
+    pub fn contains(self, layer: Layer) -> bool {
+        self.0 & (1 << layer as u32) != 0
+    }
+
+    pub fn intersects(self, other: LayerMask) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+/// The layers an entity is on and the layers it interacts with.
+///
+/// Entities without a collider are solid and interact with everything.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collider {
+    pub layers: LayerMask,
+    pub mask: LayerMask,
+}
+
+impl Collider {
+    pub fn new(layers: &[Layer], mask: &[Layer]) -> Self {
+        Collider {
+            layers: LayerMask::of(layers),
+            mask: LayerMask::of(mask),
+        }
+    }
+
+    /// Only solid colliders push back, and only against entities that have solid in their mask.
+    pub fn blocks(&self, other: &Collider) -> bool {
+        self.layers.contains(Layer::Solid) && other.mask.contains(Layer::Solid)
+    }
+
+    /// Overlaps are only reported if both sides are interested in each other.
+    pub fn interacts(&self, other: &Collider) -> bool {
+        self.mask.intersects(other.layers) && other.mask.intersects(self.layers)
+    }
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Collider {
+            layers: LayerMask::of(&[Layer::Solid]),
+            mask: LayerMask::ALL,
+        }
+    }
+}
+
+pub struct Requires(pub Requirement);
+
+pub struct Door(pub String);
+
+/// A dynamic solid that other entities can stand on and push around.
+pub struct Pushable {
+    /// Multiplier of the friction of the surface it stands on.
+    pub friction: f32,
+}
+
+/// Sets the flag while anything it interacts with is on it and clears it again when left.
+pub struct Switch {
+    pub flag: String,
+}
+
+pub struct Collectible {
+    pub index: usize,
+    pub kind: CollectibleKind,
+    pub name: String,
+}
 
 pub struct TextContainer {
     pub value: String,
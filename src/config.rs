@@ -1,4 +1,9 @@
-#[derive(Deserialize, Debug)]
+use std::collections::HashMap;
+
+use crate::components::Material;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct PlayerConfig {
     pub acceleration: f32,
     pub jump_acceleration: f32,
@@ -6,6 +11,11 @@ pub struct PlayerConfig {
     pub size: f32,
     pub float_modifier: f32,
     pub allow_air_control: bool,
+    pub max_health: i32,
+    pub invulnerability_ticks: i32,
+    pub knockback: f32,
+    /// Pixels per tick while climbing, optional like the rest of the player config.
+    pub climb_speed: f32,
 }
 
 impl Default for PlayerConfig {
@@ -17,11 +27,15 @@ impl Default for PlayerConfig {
             size: 32.0,
             float_modifier: 0.5,
             allow_air_control: false,
+            max_health: 3,
+            invulnerability_ticks: 90,
+            knockback: 6.0,
+            climb_speed: 3.0,
         }
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct CameraConfig {
     pub deadzone: f32,
 }
@@ -32,22 +46,13 @@ impl Default for CameraConfig {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug, Default)]
 pub struct DebugConfig {
     pub draw_grid: bool,
     pub draw_bounds: bool,
 }
 
-impl Default for DebugConfig {
-    fn default() -> Self {
-        DebugConfig {
-            draw_grid: false,
-            draw_bounds: false,
-        }
-    }
-}
-
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct PhysicsConfig {
     pub max_horizontal_velocity: f32,
     pub max_vertical_velocity: f32,
@@ -55,6 +60,8 @@ pub struct PhysicsConfig {
     pub normal_force: f32,
     pub gravity: f32,
     pub movement_deadzone: f32,
+    #[serde(default)]
+    pub water: WaterConfig,
 }
 
 impl Default for PhysicsConfig {
@@ -66,14 +73,189 @@ impl Default for PhysicsConfig {
             normal_force: 1.0,
             gravity: 0.2,
             movement_deadzone: 0.0001,
+            water: WaterConfig::default(),
+        }
+    }
+}
+
+/// Replaces gravity, friction and the maximum velocities while swimming.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct WaterConfig {
+    /// Counters gravity, entities float up if it's stronger.
+    pub buoyancy: f32,
+    /// Share of the velocity lost every tick.
+    pub drag: f32,
+    pub max_horizontal_velocity: f32,
+    pub max_vertical_velocity: f32,
+    /// Like `PlayerConfig::jump_acceleration`, for the strokes that replace jumps under water.
+    pub stroke: f32,
+    /// How long the player can stay under water before drowning, 0 disables the air meter.
+    pub air_ticks: usize,
+}
+
+impl Default for WaterConfig {
+    fn default() -> Self {
+        WaterConfig {
+            buoyancy: 0.17,
+            drag: 0.05,
+            max_horizontal_velocity: 3.0,
+            max_vertical_velocity: 4.0,
+            stroke: 30.0,
+            air_ticks: 600,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EnemyConfig {
+    pub speed: f32,
+    pub chase_speed: f32,
+    pub stomp_bounce: f32,
+}
+
+impl Default for EnemyConfig {
+    fn default() -> Self {
+        EnemyConfig {
+            speed: 1.5,
+            chase_speed: 2.5,
+            stomp_bounce: 6.0,
         }
     }
 }
 
-#[derive(Deserialize, Debug, Default)]
+#[derive(Deserialize, Clone, Debug)]
+pub struct AudioConfig {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig {
+            master_volume: 0.8,
+            music_volume: 0.6,
+            sfx_volume: 1.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ParticleConfig {
+    pub enabled: bool,
+    pub max_particles: usize,
+    pub dust_speed: f32,
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        ParticleConfig {
+            enabled: true,
+            max_particles: 2000,
+            dust_speed: 2.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BotConfig {
+    /// Number of ticks each input is held before the bot picks the next one.
+    pub action_ticks: usize,
+    pub max_nodes: usize,
+    /// Weight of the distance to the target, 1.0 finds the fastest route but explores much more.
+    pub greed: f32,
+    /// Positions and velocities closer than this are treated as the same state.
+    pub position_step: f32,
+    pub velocity_step: f32,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        BotConfig {
+            action_ticks: 4,
+            max_nodes: 200_000,
+            greed: 2.0,
+            position_step: 2.0,
+            velocity_step: 0.5,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CrateConfig {
+    pub mass: f32,
+    /// Relative to `PhysicsConfig::friction`, crates don't move if it's too high to push them.
+    pub friction: f32,
+}
+
+impl Default for CrateConfig {
+    fn default() -> Self {
+        CrateConfig {
+            mass: 10.0,
+            friction: 0.3,
+        }
+    }
+}
+
+/// Friction is relative to `PhysicsConfig::friction`, speed to the maximum horizontal velocity.
+/// Conveyors move entities standing on them with the given velocity along the surface.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MaterialConfig {
+    pub friction: f32,
+    pub restitution: f32,
+    pub speed: f32,
+    pub conveyor: f32,
+    pub color: Option<[u8; 3]>,
+}
+
+impl MaterialConfig {
+    pub fn material(&self) -> Material {
+        Material {
+            friction: self.friction,
+            restitution: self.restitution,
+            speed: self.speed,
+            conveyor: self.conveyor,
+        }
+    }
+}
+
+impl Default for MaterialConfig {
+    fn default() -> Self {
+        let material = Material::default();
+        MaterialConfig {
+            friction: material.friction,
+            restitution: material.restitution,
+            speed: material.speed,
+            conveyor: material.conveyor,
+            color: None,
+        }
+    }
+}
+
+/// Surface materials by the name platforms refer to them with, the shipped ones are defined in
+/// `resources/config.toml`.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct MaterialsConfig(pub HashMap<String, MaterialConfig>);
+
+#[derive(Deserialize, Clone, Debug, Default)]
 pub struct Config {
     pub player: PlayerConfig,
+    #[serde(default)]
+    pub enemy: EnemyConfig,
     pub camera: CameraConfig,
     pub physics: PhysicsConfig,
     pub debug: DebugConfig,
+    #[serde(default)]
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub particles: ParticleConfig,
+    #[serde(default)]
+    pub bot: BotConfig,
+    #[serde(default)]
+    pub materials: MaterialsConfig,
+    #[serde(default)]
+    pub crates: CrateConfig,
 }
@@ -1,30 +1,32 @@
-use ggez::event::{quit, KeyCode, KeyMods};
+use ggez::event::{KeyCode, KeyMods};
 
 use crate::common::*;
 use crate::components::*;
+use crate::events::GameEvent;
 use crate::physics::*;
 
 impl GameState {
     #[inline(always)]
-    pub fn do_movement(&mut self, _ctx: &mut Context) -> GameResult<()> {
-        for (id, (acceleration, grounded, gravity, velocity, mass, _)) in
+    pub fn do_movement(&mut self) -> GameResult<()> {
+        if self.controls.debug_pressed {
+            self.events.publish(GameEvent::DebugToggled);
+        }
+
+        for (id, (acceleration, grounded, gravity, velocity, mass, _, swimming, climbing)) in
             &mut self
                 .world
-                .query::<(&mut Acceleration, &mut Grounded, &mut Gravity, &Velocity, &Mass, &Player)>()
+                .query::<(&mut Acceleration, &mut Grounded, &mut Gravity, &Velocity, &Mass, &Player, Option<&Swimming>, Option<&Climbing>)>()
         {
             if self.controls.reset_pressed {
-                self.restart_level = true;
+                self.events.publish(GameEvent::RestartRequested);
                 break;
             }
 
-            if self.controls.debug_pressed {
-                self.config.debug.draw_bounds = !self.config.debug.draw_bounds;
-                self.config.debug.draw_grid = !self.config.debug.draw_grid;
-            }
-
             let is_grounded =  grounded.0 > 0;
+            let is_swimming = swimming.is_some();
+            let is_climbing = climbing.is_some();
 
-            if is_grounded || self.config.player.allow_air_control {
+            if is_grounded || is_swimming || self.config.player.allow_air_control {
                 if self.controls.left_held {
                     acceleration
                         .apply_force(&Vector2::new(-self.config.player.acceleration, 0.0), mass.0);
@@ -36,8 +38,16 @@ impl GameState {
                 }
             }
 
-            if is_grounded && self.controls.jump_pressed {
+            if is_swimming && self.controls.jump_pressed {
+                // swim strokes can be repeated as often as jump is pressed
+                grounded.0 = -1;
+                acceleration.apply_force(
+                    &Vector2::new(0.0, -self.config.physics.water.stroke),
+                    mass.0,
+                );
+            } else if (is_grounded || is_climbing) && self.controls.jump_pressed {
                 grounded.0 = -1;
+                self.events.publish(GameEvent::Jumped(id));
                 let mag = velocity.0.magnitude();
                 acceleration.apply_force(
                     &Vector2::new(
@@ -60,13 +70,12 @@ impl GameState {
     #[inline(always)]
     pub fn map_key_down_event(
         &mut self,
-        ctx: &mut Context,
+        _ctx: &mut Context,
         keycode: KeyCode,
         _keymods: KeyMods,
         repeat: bool,
     ) {
         match keycode {
-            KeyCode::Escape => quit(ctx),
             KeyCode::A | KeyCode::Left => {
                 self.controls.left_pressed = !repeat;
                 self.controls.left_held = true;
@@ -0,0 +1,155 @@
+use ggez::graphics::Rect;
+
+use crate::common::*;
+use crate::components::*;
+use crate::level::Layer;
+
+#[inline(always)]
+fn translated(position: &Position, bbox: &Rect) -> Rect {
+    let mut bbox = *bbox;
+    bbox.translate(Vector2::new(position.0.x, position.0.y));
+    bbox
+}
+
+impl GameState {
+    #[inline(always)]
+    pub fn update_enemies(&mut self) -> GameResult<()> {
+        let player_position = self
+            .world
+            .query::<(&Position, &Player)>()
+            .iter()
+            .map(|(_id, (position, _))| position.0)
+            .next();
+
+        let solids = self
+            .world
+            .query::<Without<Position, (&BoundingBox, Option<&Collider>)>>()
+            .iter()
+            .filter(|(_id, (_, collider))| collider.is_none_or(|c| c.layers.contains(Layer::Solid)))
+            .map(|(_id, (BoundingBox(bbox), _))| *bbox)
+            .collect::<Vec<_>>();
+
+        for (_id, (ai, position, velocity, BoundingBox(bbox), grounded)) in
+            &mut self.world.query::<(
+                &mut EnemyAi,
+                &Position,
+                &mut Velocity,
+                &BoundingBox,
+                Option<&Grounded>,
+            )>()
+        {
+            let is_grounded = grounded.is_some_and(|g| g.0 > 0);
+
+            let chase_target = player_position
+                .filter(|player| (*player - position.0).magnitude() <= ai.chase_radius);
+
+            let (mut direction, speed) = if let Some(player) = chase_target {
+                (
+                    (player.x - position.0.x).signum(),
+                    self.config.enemy.chase_speed,
+                )
+            } else if let Some(&target) = ai.patrol.get(ai.target) {
+                if (target - position.0.x).abs() <= self.config.enemy.speed {
+                    ai.target = (ai.target + 1) % ai.patrol.len();
+                }
+                ((target - position.0.x).signum(), self.config.enemy.speed)
+            } else {
+                (ai.direction, self.config.enemy.speed)
+            };
+
+            if is_grounded && direction != 0.0 {
+                let bbox = translated(position, bbox);
+                let front = if direction > 0.0 {
+                    bbox.right() + 1.0
+                } else {
+                    bbox.left() - 1.0
+                };
+
+                let wall = Point2::new(front, bbox.top() + bbox.h / 2.0);
+                let ledge = Point2::new(front, bbox.bottom() + 1.0);
+
+                let hits_wall = solids.iter().any(|solid| solid.contains(wall));
+                let has_floor = solids.iter().any(|solid| solid.contains(ledge));
+
+                if hits_wall || !has_floor {
+                    if chase_target.is_some() {
+                        // wait at the edge instead of following the player off a ledge
+                        direction = 0.0;
+                    } else {
+                        direction = -direction;
+                        if !ai.patrol.is_empty() {
+                            ai.target = (ai.target + 1) % ai.patrol.len();
+                        }
+                    }
+                }
+            }
+
+            if direction != 0.0 {
+                ai.direction = direction;
+            }
+
+            if is_grounded {
+                velocity.0.x = direction * speed;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn enemy_contact(&mut self) -> GameResult<()> {
+        let mut stomped = vec![];
+        let mut hits = vec![];
+
+        for (id, (position, velocity, BoundingBox(bbox), collider, _)) in &mut self.world.query::<(
+            &Position,
+            &mut Velocity,
+            &BoundingBox,
+            Option<&Collider>,
+            &Player,
+        )>(
+        ) {
+            let player_bbox = translated(position, bbox);
+            let collider = collider.copied().unwrap_or_default();
+
+            for (enemy, (enemy_position, BoundingBox(enemy_bbox), damage, enemy_collider, _)) in
+                &mut self.world.query::<(
+                    &Position,
+                    &BoundingBox,
+                    Option<&Damage>,
+                    Option<&Collider>,
+                    &EnemyAi,
+                )>()
+            {
+                let enemy_bbox = translated(enemy_position, enemy_bbox);
+                if !enemy_bbox.overlaps(&player_bbox)
+                    || !collider.interacts(&enemy_collider.copied().unwrap_or_default())
+                {
+                    continue;
+                }
+
+                if velocity.0.y > 0.0
+                    && player_bbox.bottom() <= enemy_bbox.top() + enemy_bbox.h / 2.0
+                {
+                    stomped.push(enemy);
+                } else {
+                    hits.push((id, enemy_position.0, damage.map_or(1, |d| d.0)));
+                }
+            }
+
+            if !stomped.is_empty() {
+                velocity.0.y = -self.config.enemy.stomp_bounce;
+            }
+        }
+
+        for id in stomped {
+            let _ = self.world.despawn(id);
+        }
+
+        for (id, source, amount) in hits {
+            self.damage(id, source, amount);
+        }
+
+        Ok(())
+    }
+}
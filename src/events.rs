@@ -0,0 +1,91 @@
+use crate::common::*;
+use crate::level::CollectibleKind;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    PlayerDied,
+    RestartRequested,
+    LevelChangeRequested(String),
+    LevelChanged {
+        from: String,
+        to: String,
+    },
+    Landed(Entity),
+    Jumped(Entity),
+    TriggerEntered {
+        trigger: Entity,
+        entity: Entity,
+    },
+    TriggerStayed {
+        trigger: Entity,
+        entity: Entity,
+    },
+    TriggerExited {
+        trigger: Entity,
+        entity: Entity,
+    },
+    CollectiblePicked {
+        level: String,
+        index: usize,
+        kind: CollectibleKind,
+        name: String,
+    },
+    DebugToggled,
+}
+
+/// Events published by the systems during a tick, drained once at the end of it.
+#[derive(Default, Debug)]
+pub struct EventQueue {
+    events: Vec<GameEvent>,
+}
+
+impl EventQueue {
+    pub fn publish(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+impl GameState {
+    /// Hands the queued events to the subscribers until none are left. Returns the level that
+    /// a restart or level change asked for, leaving it is up to the caller so the entities the
+    /// events refer to stay valid until all of them are handled.
+    pub fn dispatch_events(&mut self) -> GameResult<Option<String>> {
+        let mut next_level = None;
+        loop {
+            let events = self.events.drain();
+            if events.is_empty() {
+                return Ok(next_level);
+            }
+
+            for event in events {
+                match &event {
+                    GameEvent::PlayerDied | GameEvent::RestartRequested if next_level.is_none() => {
+                        next_level = Some(self.current_level.clone());
+                    }
+                    GameEvent::LevelChangeRequested(level) => next_level = Some(level.clone()),
+                    _ => {}
+                }
+
+                self.handle_level_event(&event);
+                self.handle_trigger_event(&event);
+                self.handle_stats_event(&event);
+                self.handle_audio_event(&event);
+                self.handle_particle_event(&event);
+                self.handle_scene_event(&event);
+                self.handle_script_event(&event)?;
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub fn process_events(&mut self) -> GameResult<()> {
+        while let Some(level) = self.dispatch_events()? {
+            self.change_level(level)?;
+        }
+        Ok(())
+    }
+}
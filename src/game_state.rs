@@ -1,16 +1,26 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use ggez::graphics;
 use maplit::hashmap;
+use rhai::AST;
 
+use crate::audio::Audio;
 use crate::common::*;
 use crate::components::*;
 use crate::config::*;
 use crate::default_levels::add_default_levels;
+use crate::events::EventQueue;
 use crate::level::*;
+use crate::particles::Particle;
+use crate::save::*;
+use crate::scenes::Scene;
+use crate::script::LevelScript;
+use crate::speedrun::*;
+use crate::stats::*;
 
 #[derive(Debug, PartialEq)]
 pub enum CameraMode {
+    #[allow(dead_code)]
     Locked,
     Free,
 }
@@ -50,145 +60,187 @@ pub struct Controls {
 
 pub struct GameState {
     pub tick: usize,
+    pub scenes: Vec<Scene>,
     pub config: Config,
     pub camera: Camera,
     pub world: World,
     pub controls: Controls,
-    pub restart_level: bool,
-    pub change_level: Option<String>,
     pub current_level: String,
     pub levels: HashMap<String, Level>,
+    pub stats: RunStats,
+    pub speedrun: Speedrun,
+    pub keys: BTreeSet<String>,
+    pub flags: HashSet<String>,
+    pub save: SaveGame,
+    /// Where the save game is written, nothing is written without one.
+    pub save_path: Option<std::path::PathBuf>,
+    pub spawn_position: Option<Point2>,
+    pub continued_run: Option<ContinuedRun>,
+    pub audio: Audio,
+    pub events: EventQueue,
+    pub trigger_overlaps: HashSet<(Entity, Entity)>,
+    pub message: Option<(String, usize)>,
+    pub script: Option<LevelScript>,
+    /// Compiled level scripts by path.
+    pub script_asts: HashMap<String, AST>,
+    pub particles: Vec<Particle>,
+    pub background_images: HashMap<String, Option<graphics::Image>>,
 }
 
-impl GameState {
-    pub fn new(ctx: &mut Context) -> ggez::GameResult<GameState> {
-        let config: Config = toml::from_str(
-            &std::fs::read_to_string("resources/config.toml")
-                .unwrap_or_else(|_| include_str!("../resources/config.toml").to_string()),
-        )
-        .unwrap_or_default();
-
-        let current_level = "start".to_string();
-        let mut levels = std::path::Path::new("resources/levels")
-            .read_dir()
-            .map(|d| {
-                d.flatten()
-                    .flat_map(|f| {
-                        if f.metadata().unwrap().is_file() {
-                            Some(f.path())
-                        } else {
-                            None
-                        }
-                    })
-                    .filter(|p| match p.extension() {
-                        Some(s) => s.to_string_lossy().to_string() == "toml",
-                        _ => false,
-                    })
-                    .map(|mut f| {
-                        let level = std::fs::read_to_string(&f)
-                            .map(|data| match toml::from_str::<Level>(&data) {
-                                Ok(level) => Some(level),
+pub fn load_config() -> Config {
+    toml::from_str(
+        &std::fs::read_to_string("resources/config.toml")
+            .unwrap_or_else(|_| include_str!("../resources/config.toml").to_string()),
+    )
+    .unwrap_or_default()
+}
+
+/// Loads the levels in `resources/levels`, falling back to the ones embedded at build time.
+pub fn load_levels() -> HashMap<String, Level> {
+    let mut levels = std::path::Path::new("resources/levels")
+        .read_dir()
+        .map(|d| {
+            d.flatten()
+                .flat_map(|f| {
+                    if f.metadata().unwrap().is_file() {
+                        Some(f.path())
+                    } else {
+                        None
+                    }
+                })
+                .filter(|p| match p.extension() {
+                    Some(s) => s == "toml",
+                    _ => false,
+                })
+                .map(|mut f| {
+                    let level = std::fs::read_to_string(&f)
+                        .map(|data| match toml::from_str::<Level>(&data) {
+                            Ok(level) => match level.validate() {
+                                Ok(()) => Some(level),
                                 Err(e) => {
                                     println!(
-                                        "failed to parse level file ({:?}) with the following error: {}",
+                                        "invalid level file ({:?}): {}",
                                         f.clone().into_os_string(), e
                                     );
                                     None
                                 }
-                            })
-                            .ok()
-                            .flatten();
-                        f.set_extension("");
-                        (
-                            f.file_name()
-                                .expect("File name is not valid utf-8!")
-                                .to_string_lossy()
-                                .to_string(),
-                            level,
-                        )
-                    })
-                    .filter(|(_, l)| l.is_some())
-                    .map(|(f, l)| (f, l.unwrap()))
-                    .collect::<HashMap<_, _>>()
-            })
-            .unwrap_or_else(|_| {
-                hashmap! {}
-            });
-
-        if levels.is_empty() {
-            if !add_default_levels(&mut levels) {
-                levels.insert("start".to_string(), Level::default());
-            }
-        }
-
-        for (_, mut v) in &mut levels {
-            v.size.height *= config.player.size;
-            v.size.width *= config.player.size;
-            v.start.x *= config.player.size;
-            v.start.y *= config.player.size;
-            let scale_platform = |p: &mut Platform| {
-                p.x *= config.player.size;
-                p.y *= config.player.size;
-                p.width *= config.player.size;
-                p.height *= config.player.size;
-            };
-            v.platforms.iter_mut().for_each(scale_platform);
-            v.traps.iter_mut().for_each(scale_platform);
-            v.teleporters.iter_mut().for_each(|p| {
-                p.x *= config.player.size;
-                p.y *= config.player.size;
-                p.width *= config.player.size;
-                p.height *= config.player.size;
-            });
-            v.texts.iter_mut().for_each(|t| {
-                t.x *= config.player.size;
-                t.y *= config.player.size;
-            });
-        }
+                            },
+                            Err(e) => {
+                                println!(
+                                    "failed to parse level file ({:?}) with the following error: {}",
+                                    f.clone().into_os_string(), e
+                                );
+                                None
+                            }
+                        })
+                        .ok()
+                        .flatten();
+                    f.set_extension("");
+                    (
+                        f.file_name()
+                            .expect("File name is not valid utf-8!")
+                            .to_string_lossy()
+                            .to_string(),
+                        level,
+                    )
+                })
+                .filter(|(_, l)| l.is_some())
+                .map(|(f, l)| (f, l.unwrap()))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_else(|_| {
+            hashmap! {}
+        });
+
+    if levels.is_empty() && !add_default_levels(&mut levels) {
+        levels.insert("start".to_string(), Level::default());
+    }
 
-        let mut world = World::new();
+    levels
+}
 
+impl GameState {
+    pub fn new(ctx: &mut Context) -> ggez::GameResult<GameState> {
+        let mut game_state = GameState::from_levels(load_config(), load_levels());
+        game_state.warn_unreachable();
+        let save_path = SaveGame::path(ctx);
+        game_state.save = SaveGame::load(&save_path);
+        game_state.save_path = Some(save_path);
+        game_state.speedrun =
+            Speedrun::load(std::path::PathBuf::from("resources/personal_best.toml"));
+        game_state.audio = Audio::new(ctx);
+
+        let config = &game_state.config;
+        let level = &game_state.levels[&game_state.current_level];
         if config.debug.draw_grid {
             let mut mb = graphics::MeshBuilder::new();
-            for i in 0..(levels[&current_level].size.width / config.player.size) as i32 + 1 {
+            for i in 0..(level.size.width / config.player.size) as i32 + 1 {
                 let start = i as f32 * config.player.size;
                 mb.line(
                     &[
                         Point2::new(start, 0.0),
-                        Point2::new(start, levels[&current_level].size.height),
+                        Point2::new(start, level.size.height),
                     ],
                     GRID_THICKNESS,
                     graphics::BLACK,
                 )?;
             }
-            for i in 0..(levels[&current_level].size.height / config.player.size) as i32 + 1 {
+            for i in 0..(level.size.height / config.player.size) as i32 + 1 {
                 let start = i as f32 * config.player.size;
                 mb.line(
                     &[
                         Point2::new(0.0, start),
-                        Point2::new(levels[&current_level].size.width, start),
+                        Point2::new(level.size.width, start),
                     ],
                     GRID_THICKNESS,
                     graphics::BLACK,
                 )?;
             }
-            world.spawn((Position::new(0.0, 0.0), mb.build(ctx)?, ZOrder(20)));
+            game_state
+                .world
+                .spawn((Position::new(0.0, 0.0), mb.build(ctx)?, ZOrder(20)));
         }
 
-        let mut game_state = GameState {
+        let current_level = game_state.current_level.clone();
+        game_state.change_level(current_level)?;
+        game_state.process_events()?;
+
+        Ok(game_state)
+    }
+
+    /// Creates a game state for levels in grid units, without a window, save game or sounds.
+    ///
+    /// The player is only spawned by the first `change_level`.
+    pub fn from_levels(config: Config, mut levels: HashMap<String, Level>) -> GameState {
+        for level in levels.values_mut() {
+            level.scale(config.player.size);
+        }
+
+        GameState {
             config,
-            world,
-            current_level: current_level.clone(),
+            world: World::new(),
+            current_level: "start".to_string(),
             levels,
-            restart_level: false,
-            change_level: None,
             camera: Camera::default(),
             controls: Controls::default(),
             tick: 0,
-        };
-
-        game_state.change_level(ctx, current_level)?;
-
-        Ok(game_state)
+            scenes: vec![Scene::Title { selected: 0 }],
+            stats: RunStats::default(),
+            speedrun: Speedrun::default(),
+            keys: BTreeSet::new(),
+            flags: HashSet::new(),
+            save: SaveGame::default(),
+            save_path: None,
+            spawn_position: None,
+            continued_run: None,
+            audio: Audio::default(),
+            events: EventQueue::default(),
+            trigger_overlaps: HashSet::new(),
+            message: None,
+            script: None,
+            script_asts: HashMap::new(),
+            particles: vec![],
+            background_images: HashMap::new(),
+        }
     }
 }
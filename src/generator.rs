@@ -0,0 +1,171 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::common::*;
+use crate::level::*;
+use crate::reachability::{analyze, JumpModel};
+
+const PLATFORM_COUNT: usize = 12;
+const LEVEL_HEIGHT: f32 = 60.0;
+const MIN_Y: f32 = 6.0;
+const MAX_Y: f32 = 40.0;
+/// Only use part of the theoretical jump distance, nobody hits a frame perfect jump every time.
+const SAFETY_MARGIN: f32 = 0.7;
+/// Levels are regenerated until the reachability analysis finds a way to the teleporter.
+const MAX_ATTEMPTS: usize = 20;
+/// The most the teleporter floats above the last platform, in grid units.
+const MAX_TELEPORTER_HOVER: f32 = 2.0;
+
+/// Generates a level in grid units, ending in a teleporter to `next_level`.
+///
+/// The same seed and config always produce the same level. Returns `None` if no attempt passes
+/// the reachability analysis.
+pub fn generate_level(seed: u64, next_level: &str, model: &JumpModel) -> Option<Level> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    (0..MAX_ATTEMPTS)
+        .map(|_| generate_attempt(&mut rng, next_level, model))
+        .find(|level| is_completable(level, model))
+}
+
+fn is_completable(level: &Level, model: &JumpModel) -> bool {
+    let mut scaled = level.clone();
+    scaled.scale(model.size);
+    let report = analyze(&scaled, model);
+    !report.start_falls && report.unreachable_teleporters.is_empty()
+}
+
+fn generate_attempt(rng: &mut StdRng, next_level: &str, model: &JumpModel) -> Level {
+    let mut platforms = vec![Platform {
+        x: 0.0,
+        y: 12.0,
+        width: 6.0,
+        height: 1.0,
+        name: None,
+        material: None,
+        collision: LevelCollision::default(),
+    }];
+    let mut traps = vec![];
+    let mut collectibles = vec![];
+
+    let max_rise = (model.max_rise() * SAFETY_MARGIN).floor();
+
+    for _ in 0..PLATFORM_COUNT {
+        let previous = &platforms[platforms.len() - 1];
+        let rise = rng
+            .gen_range(-4.0, max_rise.max(0.0) + 1.0f32)
+            .floor()
+            .max(MIN_Y - previous.y)
+            .min(MAX_Y - previous.y);
+
+        let max_gap = model
+            .reach(rise)
+            .map_or(1.0, |reach| (reach * SAFETY_MARGIN).floor().max(1.0));
+        let gap = rng.gen_range(1.0, max_gap + 1.0f32).floor();
+        let width = rng.gen_range(2.0, 7.0f32).floor();
+
+        let platform = Platform {
+            x: previous.x + previous.width + gap,
+            y: previous.y + rise,
+            width,
+            height: 1.0,
+            name: None,
+            material: None,
+            collision: LevelCollision::default(),
+        };
+
+        if width >= 5.0 && rng.gen_bool(0.3) {
+            traps.push(Trap {
+                x: platform.x + (width / 2.0).floor(),
+                y: platform.y + 1.0,
+                width: 1.0,
+                height: 1.0,
+                damage: 1,
+                collision: LevelCollision::default(),
+            });
+        } else if rng.gen_bool(0.4) {
+            collectibles.push(LevelCollectible {
+                x: platform.x + width / 2.0,
+                y: platform.y + 2.0,
+                kind: if rng.gen_bool(0.1) {
+                    CollectibleKind::Gem
+                } else {
+                    CollectibleKind::Coin
+                },
+                name: String::new(),
+            });
+        }
+
+        platforms.push(platform);
+    }
+
+    // the bottom of the teleporter is within jumping height of the last platform
+    let hover = (model.max_rise() * SAFETY_MARGIN)
+        .floor()
+        .clamp(0.0, MAX_TELEPORTER_HOVER);
+    let last = &platforms[platforms.len() - 1];
+    let teleporters = vec![Teleporter {
+        x: last.x + last.width - 2.0,
+        y: last.y + 1.0 + hover,
+        width: 2.0,
+        height: 1.0,
+        target: next_level.to_string(),
+        requires: None,
+        collision: LevelCollision::default(),
+    }];
+    let width = last.x + last.width + 10.0;
+
+    Level {
+        size: LevelSize {
+            width,
+            height: LEVEL_HEIGHT,
+        },
+        start: Start { x: 2.0, y: 12.0 },
+        platforms,
+        traps,
+        teleporters,
+        collectibles,
+        ..Level::default()
+    }
+}
+
+/// Serializes a level in the same format as the files in `resources/levels`.
+pub fn export_level(level: &Level) -> Result<String, toml::ser::Error> {
+    // going through a value sorts plain values before tables
+    toml::Value::try_from(level).and_then(|value| toml::to_string(&value))
+}
+
+impl GameState {
+    pub fn play_generated_level(&mut self, seed: u64) {
+        let name = "generated".to_string();
+        let model = match JumpModel::from_config(&self.config) {
+            Ok(model) => model,
+            Err(e) => {
+                println!("can't generate a level: {}", e);
+                return;
+            }
+        };
+        let mut level = match generate_level(seed, "end", &model) {
+            Some(level) => level,
+            None => {
+                println!("no completable level found for seed {}", seed);
+                return;
+            }
+        };
+
+        match export_level(&level) {
+            Ok(data) => {
+                let path = "resources/generated_level.toml";
+                match std::fs::write(path, data) {
+                    Ok(_) => println!("generated level with seed {} ({})", seed, path),
+                    Err(e) => println!("failed to export generated level: {}", e),
+                }
+            }
+            Err(e) => println!("failed to serialize generated level: {}", e),
+        }
+
+        level.scale(self.config.player.size);
+        self.levels.insert(name.clone(), level);
+        self.play_level(name);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::common::*;
+use crate::components::*;
+use crate::events::GameEvent;
+use crate::physics::*;
+
+impl GameState {
+    /// Damages an entity, knocking it away from `source`.
+    ///
+    /// Entities without health die instantly, invulnerable entities are ignored.
+    pub fn damage(&mut self, id: Entity, source: Point2, amount: i32) {
+        if self.world.get::<Invulnerable>(id).is_ok() {
+            return;
+        }
+
+        let alive = match self.world.get_mut::<Health>(id) {
+            Ok(mut health) => {
+                health.current -= amount;
+                health.current > 0
+            }
+            Err(_) => false,
+        };
+
+        if !alive {
+            if self.world.get::<Player>(id).is_ok() {
+                self.events.publish(GameEvent::PlayerDied);
+            } else {
+                let _ = self.world.despawn(id);
+            }
+            return;
+        }
+
+        if let Ok(mut q) = self.world.query_one::<(&Position, &mut Velocity)>(id) {
+            if let Some((position, velocity)) = q.get() {
+                let direction = Vector2::new((position.0.x - source.x).signum(), -1.0);
+                velocity.0 = direction.normalize_safe() * self.config.player.knockback;
+            }
+        }
+
+        let _ = self
+            .world
+            .insert_one(id, Invulnerable(self.config.player.invulnerability_ticks));
+    }
+
+    #[inline(always)]
+    pub fn update_invulnerability(&mut self) -> GameResult<()> {
+        let mut expired = vec![];
+        for (id, invulnerable) in &mut self.world.query::<&mut Invulnerable>() {
+            invulnerable.0 -= 1;
+            if invulnerable.0 <= 0 {
+                expired.push(id);
+            }
+        }
+
+        for id in expired {
+            let _ = self.world.remove_one::<Invulnerable>(id);
+        }
+
+        Ok(())
+    }
+}
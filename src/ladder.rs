@@ -0,0 +1,147 @@
+use ggez::graphics::Rect;
+
+use crate::common::*;
+use crate::components::*;
+use crate::events::GameEvent;
+
+impl GameState {
+    fn ladders(&self) -> Vec<Rect> {
+        self.world
+            .query::<(&BoundingBox, &Ladder)>()
+            .iter()
+            .map(|(_id, (BoundingBox(bbox), _))| *bbox)
+            .collect()
+    }
+
+    /// Up attaches the player to a ladder it is in front of, down to one it stands on. While
+    /// climbing, gravity is off and the player moves at the climb speed until it jumps, leaves
+    /// the ladder or climbs over its top.
+    #[inline(always)]
+    pub fn climb_ladders(&mut self) -> GameResult<()> {
+        let ladders = self.ladders();
+        let speed = self.config.player.climb_speed;
+        let controls = &self.controls;
+        let mut attached = vec![];
+        let mut detached = vec![];
+
+        for (
+            id,
+            (position, &BoundingBox(mut bbox), velocity, acceleration, gravity, climbing, _),
+        ) in &mut self.world.query::<(
+            &mut Position,
+            &BoundingBox,
+            &mut Velocity,
+            &mut Acceleration,
+            &mut Gravity,
+            Option<&Climbing>,
+            &Player,
+        )>() {
+            bbox.translate(Vector2::new(position.0.x, position.0.y));
+            let center = bbox.x + bbox.w / 2.0;
+            let ladder = ladders.iter().find(|ladder| {
+                center >= ladder.left()
+                    && center <= ladder.right()
+                    && bbox.bottom() >= ladder.top()
+                    && bbox.top() <= ladder.bottom()
+            });
+
+            let climb = match ladder {
+                Some(_) if controls.jump_pressed => false,
+                Some(_) if climbing.is_some() => true,
+                Some(ladder) => {
+                    (controls.up_held && bbox.bottom() > ladder.top())
+                        || (controls.down_held && bbox.bottom() - ladder.top() <= 1.0)
+                }
+                None => false,
+            };
+
+            if !climb {
+                if climbing.is_some() {
+                    gravity.0.y = self.config.physics.gravity;
+                    detached.push(id);
+                }
+                continue;
+            }
+
+            let direction = |negative: bool, positive: bool| match (negative, positive) {
+                (true, false) => -1.0,
+                (false, true) => 1.0,
+                _ => 0.0,
+            };
+            velocity.0 = Vector2::new(
+                direction(controls.left_held, controls.right_held),
+                direction(controls.up_held, controls.down_held),
+            ) * speed;
+            acceleration.0 *= 0.0;
+            gravity.0.y = 0.0;
+
+            // climbing over the top leaves the player standing on the ladder
+            let top = ladder.map_or(bbox.top(), |ladder| ladder.top());
+            if velocity.0.y < 0.0 && bbox.bottom() + velocity.0.y <= top {
+                position.0.y += top - bbox.bottom();
+                velocity.0.y = 0.0;
+                gravity.0.y = self.config.physics.gravity;
+                if climbing.is_some() {
+                    detached.push(id);
+                }
+            } else if climbing.is_none() {
+                attached.push(id);
+            }
+        }
+
+        for id in attached {
+            let _ = self.world.insert_one(id, Climbing);
+        }
+        for id in detached {
+            let _ = self.world.remove_one::<Climbing>(id);
+        }
+
+        Ok(())
+    }
+
+    /// The tops of ladders are like platforms that can only be landed on from above, unless the
+    /// player holds down to climb down.
+    #[inline(always)]
+    pub fn stand_on_ladders(&mut self) -> GameResult<()> {
+        if self.controls.down_held {
+            return Ok(());
+        }
+
+        let ladders = self.ladders();
+        let mut landed = vec![];
+
+        for (id, (position, &BoundingBox(relative), velocity, _)) in &mut self
+            .world
+            .query::<Without<Climbing, (&mut Position, &BoundingBox, &mut Velocity, &Player)>>()
+        {
+            if velocity.0.y < 0.0 {
+                continue;
+            }
+
+            let center = position.0.x + relative.x + relative.w / 2.0;
+            let feet = position.0.y + relative.bottom();
+            if let Some(ladder) = ladders.iter().find(|ladder| {
+                center >= ladder.left()
+                    && center <= ladder.right()
+                    && feet >= ladder.top()
+                    && feet <= ladder.top() + velocity.0.y + 1.0
+            }) {
+                position.0.y = ladder.top() - relative.bottom();
+                velocity.0.y = 0.0;
+                landed.push(id);
+            }
+        }
+
+        for id in landed {
+            if let Ok(mut grounded) = self.world.get_mut::<Grounded>(id) {
+                if grounded.0 <= 0 {
+                    self.events.publish(GameEvent::Landed(id));
+                }
+                grounded.0 = COYOTE_TIME_IN_TICKS;
+            }
+            let _ = self.world.insert_one(id, Ground::default());
+        }
+
+        Ok(())
+    }
+}
@@ -1,17 +1,30 @@
 use ggez::graphics;
-use ggez::graphics::{Color, DrawMode, Rect};
+use ggez::graphics::{Color, Rect};
 
 use crate::common::*;
 use crate::components::*;
+use crate::events::GameEvent;
+use crate::stats::*;
 
 impl GameState {
     #[inline(always)]
-    pub fn restart_level(&mut self, ctx: &mut Context) -> GameResult<()> {
-        self.change_level(ctx, self.current_level.clone())
-    }
+    pub fn change_level(&mut self, new_level: String) -> GameResult<()> {
+        let previous_level = self.current_level.clone();
+        self.stats.leave_level(&self.current_level, self.tick);
+        self.speedrun
+            .change_level(&self.current_level, &new_level, self.tick);
+        if let Some(run) = self.continued_run.take() {
+            self.stats = run.stats;
+            self.stats.level_started = self.tick;
+            self.keys = run.keys;
+            self.flags.clear();
+        } else if new_level == "start" && self.current_level != "start" {
+            self.stats = RunStats::default();
+            self.stats.level_started = self.tick;
+            self.keys.clear();
+            self.flags.clear();
+        }
 
-    #[inline(always)]
-    pub fn change_level(&mut self, ctx: &mut Context, new_level: String) -> GameResult<()> {
         let current_level_atom = DefaultAtom::from(self.current_level.clone());
         let ids = self
             .world
@@ -26,30 +39,43 @@ impl GameState {
         }
 
         self.current_level = new_level;
+        self.stats.enter_level(
+            &self.current_level,
+            self.levels[&self.current_level].collectibles.len(),
+            self.tick,
+        );
 
         let current_level_atom = DefaultAtom::from(self.current_level.clone());
-        let start_x = self.levels[&self.current_level].start.x;
-        let start_y = self.levels[&self.current_level].size.height
-            - (self.levels[&self.current_level].start.y)
-            - 1.0;
+        let start = self.spawn_position.take().unwrap_or_else(|| {
+            Point2::new(
+                self.levels[&self.current_level].start.x,
+                self.levels[&self.current_level].size.height
+                    - (self.levels[&self.current_level].start.y)
+                    - 1.0,
+            )
+        });
         self.world.spawn((
             Player,
-            Position::new(start_x, start_y),
+            Position(start),
             Acceleration::new(0.0, 0.0),
             Velocity::new(0.0, 0.0),
             Mass(self.config.player.mass),
+            Grounded(0),
+            Air(self.config.physics.water.air_ticks),
+            Health {
+                current: self.config.player.max_health,
+                max: self.config.player.max_health,
+            },
             Gravity(Vector2::new(0.0, self.config.physics.gravity)),
-            graphics::Mesh::new_rectangle(
-                ctx,
-                graphics::DrawMode::fill(),
-                Rect::new(
+            Shape {
+                rect: Rect::new(
                     -(self.config.player.size / 2.0),
                     -self.config.player.size,
                     self.config.player.size,
                     self.config.player.size,
                 ),
-                Color::from_rgb(0, 0, 255),
-            )?,
+                color: Color::from_rgb(0, 0, 255),
+            },
             ZOrder(0),
             BoundingBox(Rect::new(
                 -(self.config.player.size / 2.0),
@@ -57,6 +83,17 @@ impl GameState {
                 self.config.player.size,
                 self.config.player.size,
             )),
+            ParticleEmitter::dust(),
+            Collider::new(
+                &[Layer::Player],
+                &[
+                    Layer::Solid,
+                    Layer::Trigger,
+                    Layer::Hazard,
+                    Layer::Enemy,
+                    Layer::Projectile,
+                ],
+            ),
             LevelId(current_level_atom.clone()),
         ));
 
@@ -65,15 +102,50 @@ impl GameState {
             let y = self.levels[&self.current_level].size.height - (platform.y);
             let width = platform.width + 1.0;
             let height = platform.height + 1.0;
-            self.world.spawn((
-                graphics::Mesh::new_rectangle(
-                    ctx,
-                    DrawMode::fill(),
-                    Rect::new(0.0, 0.0, width, height),
-                    graphics::BLACK,
-                )?,
+            let (material, color) = self.surface_material(&platform.material);
+            let id = self.world.spawn((
+                Shape {
+                    rect: Rect::new(0.0, 0.0, width, height),
+                    color,
+                },
                 ZOrder(20),
                 BoundingBox(Rect::new(x, y, width, height)),
+                platform.collision.collider(&[Layer::Solid], &[]),
+                material,
+                LevelId(current_level_atom.clone()),
+            ));
+
+            if let Some(name) = &platform.name {
+                let _ = self.world.insert_one(id, Name(name.clone()));
+            }
+        }
+
+        for slope in &self.levels[&self.current_level].slopes {
+            let level_height = self.levels[&self.current_level].size.height;
+            let width = slope.width + 1.0;
+            let left = level_height - slope.left;
+            let right = level_height - slope.right;
+            let top = left.min(right);
+            let height = left.max(right) - top + slope.height + 1.0;
+            let (material, color) = self.surface_material(&slope.material);
+            self.world.spawn((
+                Polygon {
+                    points: vec![
+                        Point2::new(0.0, left - top),
+                        Point2::new(width, right - top),
+                        Point2::new(width, height),
+                        Point2::new(0.0, height),
+                    ],
+                    color,
+                },
+                ZOrder(20),
+                BoundingBox(Rect::new(slope.x, top, width, height)),
+                Slope {
+                    start: Point2::new(slope.x, left),
+                    end: Point2::new(slope.x + width, right),
+                },
+                slope.collision.collider(&[Layer::Solid], &[]),
+                material,
                 LevelId(current_level_atom.clone()),
             ));
         }
@@ -84,15 +156,22 @@ impl GameState {
             let width = trap.width + 1.0;
             let height = trap.height + 1.0;
             self.world.spawn((
-                graphics::Mesh::new_rectangle(
-                    ctx,
-                    DrawMode::fill(),
-                    Rect::new(0.0, 0.0, width, height),
-                    Color::from_rgb(255, 0, 0),
-                )?,
+                Shape {
+                    rect: Rect::new(0.0, 0.0, width, height),
+                    color: Color::from_rgb(255, 0, 0),
+                },
                 ZOrder(20),
                 BoundingBox(Rect::new(x, y, width, height)),
-                Death,
+                Trigger {
+                    on_enter: vec![TriggerAction::Damage {
+                        amount: trap.damage,
+                    }],
+                    on_stay: vec![TriggerAction::Damage {
+                        amount: trap.damage,
+                    }],
+                    on_exit: vec![],
+                },
+                trap.collision.collider(&[Layer::Hazard], &[Layer::Player]),
                 LevelId(current_level_atom.clone()),
             ));
         }
@@ -103,17 +182,218 @@ impl GameState {
             let width = teleporter.width + 1.0;
             let height = teleporter.height + 1.0;
 
+            let color = if teleporter.requires.is_some() {
+                Color::from_rgb(0, 128, 0)
+            } else {
+                Color::from_rgb(0, 255, 0)
+            };
+
+            let id = self.world.spawn((
+                Shape {
+                    rect: Rect::new(0.0, 0.0, width, height),
+                    color,
+                },
+                ZOrder(20),
+                BoundingBox(Rect::new(x, y, width, height)),
+                LevelId(current_level_atom.clone()),
+                Trigger {
+                    on_enter: vec![TriggerAction::Teleport {
+                        level: teleporter.target.clone(),
+                    }],
+                    on_stay: vec![],
+                    on_exit: vec![],
+                },
+                teleporter
+                    .collision
+                    .collider(&[Layer::Trigger], &[Layer::Player]),
+            ));
+
+            if let Some(requirement) = &teleporter.requires {
+                let _ = self.world.insert_one(id, Requires(requirement.clone()));
+            }
+        }
+
+        for trigger in &self.levels[&self.current_level].triggers {
+            let x = trigger.x;
+            let y = self.levels[&self.current_level].size.height - trigger.y;
+            let width = trigger.width + 1.0;
+            let height = trigger.height + 1.0;
+
+            let id = self.world.spawn((
+                BoundingBox(Rect::new(x, y, width, height)),
+                LevelId(current_level_atom.clone()),
+                Trigger {
+                    on_enter: trigger.on_enter.clone(),
+                    on_stay: trigger.on_stay.clone(),
+                    on_exit: trigger.on_exit.clone(),
+                },
+                trigger
+                    .collision
+                    .collider(&[Layer::Trigger], &[Layer::Player]),
+            ));
+
+            if let Some(requirement) = &trigger.requires {
+                let _ = self.world.insert_one(id, Requires(requirement.clone()));
+            }
+
+            if let Some(name) = &trigger.name {
+                let _ = self.world.insert_one(id, Name(name.clone()));
+            }
+        }
+
+        for door in &self.levels[&self.current_level].doors {
+            let x = door.x;
+            let y = self.levels[&self.current_level].size.height - door.y;
+            let width = door.width + 1.0;
+            let height = door.height + 1.0;
+
             self.world.spawn((
-                graphics::Mesh::new_rectangle(
-                    ctx,
-                    DrawMode::fill(),
-                    Rect::new(0.0, 0.0, width, height),
-                    Color::from_rgb(0, 255, 0),
-                )?,
+                Shape {
+                    rect: Rect::new(0.0, 0.0, width, height),
+                    color: Color::from_rgb(139, 69, 19),
+                },
                 ZOrder(20),
                 BoundingBox(Rect::new(x, y, width, height)),
+                Door(door.key.clone()),
+                door.collision.collider(&[Layer::Solid], &[]),
+                LevelId(current_level_atom.clone()),
+            ));
+        }
+
+        for level_crate in &self.levels[&self.current_level].crates {
+            let x = level_crate.x + level_crate.width / 2.0;
+            let y = self.levels[&self.current_level].size.height - level_crate.y - 1.0;
+            let bbox = Rect::new(
+                -(level_crate.width / 2.0),
+                -level_crate.height,
+                level_crate.width,
+                level_crate.height,
+            );
+
+            self.world.spawn((
+                Pushable {
+                    friction: self.config.crates.friction,
+                },
+                Position::new(x, y),
+                Acceleration::new(0.0, 0.0),
+                Velocity::new(0.0, 0.0),
+                Mass(self.config.crates.mass),
+                Gravity(Vector2::new(0.0, self.config.physics.gravity)),
+                Shape {
+                    rect: bbox,
+                    color: Color::from_rgb(181, 136, 79),
+                },
+                ZOrder(10),
+                BoundingBox(bbox),
+                level_crate.collision.collider(
+                    &[Layer::Solid, Layer::Crate],
+                    &[Layer::Solid, Layer::Trigger, Layer::Hazard],
+                ),
+                LevelId(current_level_atom.clone()),
+            ));
+        }
+
+        for water in &self.levels[&self.current_level].water {
+            let x = water.x;
+            let y = self.levels[&self.current_level].size.height - water.y;
+            let width = water.width + 1.0;
+            let height = water.height + 1.0;
+
+            self.world.spawn((
+                Shape {
+                    rect: Rect::new(0.0, 0.0, width, height),
+                    color: Color::new(0.2, 0.5, 1.0, 0.4),
+                },
+                ZOrder(30),
+                BoundingBox(Rect::new(x, y, width, height)),
+                Water,
+                Collider::new(&[], &[]),
+                LevelId(current_level_atom.clone()),
+            ));
+        }
+
+        for ladder in &self.levels[&self.current_level].ladders {
+            let x = ladder.x;
+            let y = self.levels[&self.current_level].size.height - ladder.y;
+            let width = ladder.width + 1.0;
+            let height = ladder.height + 1.0;
+
+            self.world.spawn((
+                Shape {
+                    rect: Rect::new(0.0, 0.0, width, height),
+                    color: Color::from_rgb(150, 100, 50),
+                },
+                ZOrder(25),
+                BoundingBox(Rect::new(x, y, width, height)),
+                Ladder,
+                Collider::new(&[], &[]),
+                LevelId(current_level_atom.clone()),
+            ));
+        }
+
+        let plate = self.config.player.size / 4.0;
+        for switch in &self.levels[&self.current_level].switches {
+            let x = switch.x;
+            let y = self.levels[&self.current_level].size.height - switch.y - plate;
+            let width = switch.width + 1.0;
+            let height = plate + 1.0;
+
+            self.world.spawn((
+                Shape {
+                    rect: Rect::new(0.0, 0.0, width, height),
+                    color: Color::from_rgb(220, 180, 40),
+                },
+                ZOrder(10),
+                BoundingBox(Rect::new(x, y, width, height)),
+                Switch {
+                    flag: switch.flag.clone(),
+                },
+                switch
+                    .collision
+                    .collider(&[Layer::Trigger], &[Layer::Player, Layer::Crate]),
+                LevelId(current_level_atom.clone()),
+            ));
+        }
+
+        for enemy in &self.levels[&self.current_level].enemies {
+            let x = enemy.x;
+            let y = self.levels[&self.current_level].size.height - enemy.y - 1.0;
+            let bbox = Rect::new(
+                -(enemy.width / 2.0),
+                -enemy.height,
+                enemy.width,
+                enemy.height,
+            );
+
+            self.world.spawn((
+                EnemyAi {
+                    patrol: enemy.patrol.clone(),
+                    target: 0,
+                    direction: -1.0,
+                    chase_radius: enemy.chase_radius,
+                },
+                Position::new(x, y),
+                Acceleration::new(0.0, 0.0),
+                Velocity::new(0.0, 0.0),
+                Gravity(Vector2::new(0.0, self.config.physics.gravity)),
+                Shape {
+                    rect: bbox,
+                    color: Color::from_rgb(128, 0, 128),
+                },
+                ZOrder(0),
+                BoundingBox(bbox),
+                Damage(enemy.damage),
+                enemy.collision.collider(
+                    &[Layer::Enemy],
+                    &[
+                        Layer::Solid,
+                        Layer::Trigger,
+                        Layer::Hazard,
+                        Layer::Player,
+                        Layer::Projectile,
+                    ],
+                ),
                 LevelId(current_level_atom.clone()),
-                TeleportTo(teleporter.target.clone()),
             ));
         }
 
@@ -132,8 +412,257 @@ impl GameState {
             ));
         }
 
+        let collectible_size = self.config.player.size / 2.0;
+        for (index, collectible) in self.levels[&self.current_level]
+            .collectibles
+            .iter()
+            .enumerate()
+        {
+            if self.stats.is_collected(&self.current_level, index) {
+                continue;
+            }
+
+            let x = collectible.x;
+            let y = self.levels[&self.current_level].size.height - collectible.y;
+            let color = match collectible.kind {
+                CollectibleKind::Coin => Color::from_rgb(255, 215, 0),
+                CollectibleKind::Gem => Color::from_rgb(0, 200, 255),
+                CollectibleKind::Key => Color::from_rgb(255, 140, 0),
+            };
+
+            self.world.spawn((
+                Shape {
+                    rect: Rect::new(0.0, 0.0, collectible_size, collectible_size),
+                    color,
+                },
+                ZOrder(10),
+                BoundingBox(Rect::new(x, y, collectible_size, collectible_size)),
+                Collectible {
+                    index,
+                    kind: collectible.kind,
+                    name: collectible.name.clone(),
+                },
+                Collider::new(&[Layer::Trigger], &[Layer::Player]),
+                LevelId(current_level_atom.clone()),
+            ));
+        }
+
+        if previous_level != self.current_level {
+            self.update_save(&previous_level);
+        }
+
+        self.trigger_overlaps.clear();
+        self.message = None;
+
+        self.load_script();
+        self.call_script("on_enter", vec![])?;
+        self.events.publish(GameEvent::LevelChanged {
+            from: previous_level,
+            to: self.current_level.clone(),
+        });
+
         Ok(())
     }
+
+    pub fn handle_level_event(&mut self, event: &GameEvent) {
+        if let GameEvent::CollectiblePicked {
+            kind: CollectibleKind::Key,
+            name,
+            ..
+        } = event
+        {
+            self.keys.insert(name.clone());
+        }
+    }
+
+    pub fn requirement_met(&self, requirement: &Requirement) -> bool {
+        match requirement {
+            Requirement::Key(key) => self.keys.contains(key),
+            Requirement::Collectibles(count) => self.stats.collected_count() >= *count as usize,
+            Requirement::Flag(flag) => self.flags.contains(flag),
+        }
+    }
+
+    /// The material a platform refers to and the color it is drawn with.
+    fn surface_material(&self, name: &Option<String>) -> (Material, Color) {
+        let name = match name {
+            Some(name) => name,
+            None => return (Material::default(), graphics::BLACK),
+        };
+        match self.config.materials.0.get(name) {
+            Some(material) => (
+                material.material(),
+                material
+                    .color
+                    .map_or(graphics::BLACK, |[r, g, b]| Color::from_rgb(r, g, b)),
+            ),
+            None => {
+                println!("unknown material {}", name);
+                (Material::default(), graphics::BLACK)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Requirement {
+    Key(String),
+    Collectibles(u32),
+    Flag(String),
+}
+
+pub fn default_message_ticks() -> usize {
+    180
+}
+
+/// Written as inline tables in level files, e.g. `on_enter = [{ type = "set_flag", flag = "lever" }]`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerAction {
+    Kill,
+    Damage {
+        #[serde(default = "default_damage")]
+        amount: i32,
+    },
+    Teleport {
+        level: String,
+    },
+    ShowText {
+        text: String,
+        #[serde(default = "default_message_ticks")]
+        ticks: usize,
+    },
+    SetFlag {
+        flag: String,
+    },
+    PlaySound {
+        sound: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectibleKind {
+    Coin,
+    Gem,
+    Key,
+}
+
+impl CollectibleKind {
+    pub fn value(self) -> u32 {
+        match self {
+            CollectibleKind::Coin => 1,
+            CollectibleKind::Gem => 5,
+            CollectibleKind::Key => 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelCollectible {
+    pub x: f32,
+    pub y: f32,
+    pub kind: CollectibleKind,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Layer {
+    Solid,
+    Trigger,
+    Hazard,
+    Player,
+    Enemy,
+    Projectile,
+    Crate,
+}
+
+/// Optional `layers` and `mask` overrides for a level element.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LevelCollision {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layers: Option<Vec<Layer>>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mask: Option<Vec<Layer>>,
+}
+
+impl LevelCollision {
+    pub fn collider(&self, layers: &[Layer], mask: &[Layer]) -> Collider {
+        Collider::new(
+            self.layers.as_deref().unwrap_or(layers),
+            self.mask.as_deref().unwrap_or(mask),
+        )
+    }
+}
+
+fn default_crate_size() -> f32 {
+    1.0
+}
+
+/// `x` and `y` are the bottom left corner.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelCrate {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default = "default_crate_size")]
+    pub width: f32,
+    #[serde(default = "default_crate_size")]
+    pub height: f32,
+    #[serde(flatten)]
+    pub collision: LevelCollision,
+}
+
+fn default_switch_width() -> f32 {
+    1.0
+}
+
+/// A plate lying on the surface at height `y`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelSwitch {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default = "default_switch_width")]
+    pub width: f32,
+    pub flag: String,
+    #[serde(flatten)]
+    pub collision: LevelCollision,
+}
+
+fn default_ladder_width() -> f32 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelLadder {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default = "default_ladder_width")]
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelWater {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelDoor {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub key: String,
+    #[serde(flatten)]
+    pub collision: LevelCollision,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -142,23 +671,97 @@ pub struct Platform {
     pub y: f32,
     pub width: f32,
     pub height: f32,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// One of the materials in the config, plain ground by default.
+    #[serde(default)]
+    pub material: Option<String>,
+    #[serde(flatten)]
+    pub collision: LevelCollision,
+}
+
+fn default_slope_height() -> f32 {
+    1.0
+}
+
+/// A ramp from `left` to `right`, the heights of its surface at both ends. Like platforms, it is
+/// solid `height` below its lower end.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelSlope {
+    pub x: f32,
+    pub width: f32,
+    pub left: f32,
+    pub right: f32,
+    #[serde(default = "default_slope_height")]
+    pub height: f32,
+    #[serde(default)]
+    pub material: Option<String>,
+    #[serde(flatten)]
+    pub collision: LevelCollision,
+}
+
+fn default_damage() -> i32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Trap {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(default = "default_damage")]
+    pub damage: i32,
+    #[serde(flatten)]
+    pub collision: LevelCollision,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelTrigger {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub requires: Option<Requirement>,
+    #[serde(default)]
+    pub on_enter: Vec<TriggerAction>,
+    #[serde(default)]
+    pub on_stay: Vec<TriggerAction>,
+    #[serde(default)]
+    pub on_exit: Vec<TriggerAction>,
+    #[serde(flatten)]
+    pub collision: LevelCollision,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct TextColor {
     pub red: u8,
     pub green: u8,
     pub blue: u8,
 }
 
-impl Default for TextColor {
-    fn default() -> Self {
-        TextColor {
-            red: 0,
-            green: 0,
-            blue: 0,
-        }
-    }
+/// Drawn behind everything else in the order they are listed.
+///
+/// Offsets are in pixels. A parallax of 0 keeps the layer fixed on screen, 1 moves it with the level.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Background {
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub gradient: Vec<TextColor>,
+    #[serde(default)]
+    pub parallax: f32,
+    #[serde(default)]
+    pub repeat_x: bool,
+    #[serde(default)]
+    pub repeat_y: bool,
+    #[serde(default)]
+    pub offset_x: f32,
+    #[serde(default)]
+    pub offset_y: f32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -178,6 +781,32 @@ pub struct Teleporter {
     pub width: f32,
     pub height: f32,
     pub target: String,
+    #[serde(default)]
+    pub requires: Option<Requirement>,
+    #[serde(flatten)]
+    pub collision: LevelCollision,
+}
+
+fn default_enemy_size() -> f32 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LevelEnemy {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default = "default_enemy_size")]
+    pub width: f32,
+    #[serde(default = "default_enemy_size")]
+    pub height: f32,
+    #[serde(default)]
+    pub patrol: Vec<f32>,
+    #[serde(default)]
+    pub chase_radius: f32,
+    #[serde(default = "default_damage")]
+    pub damage: i32,
+    #[serde(flatten)]
+    pub collision: LevelCollision,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -198,15 +827,145 @@ pub struct Level {
     pub start: Start,
     #[serde(rename = "platform")]
     pub platforms: Vec<Platform>,
+    #[serde(rename = "slope")]
+    #[serde(default)]
+    pub slopes: Vec<LevelSlope>,
     #[serde(rename = "trap")]
     #[serde(default)]
-    pub traps: Vec<Platform>,
+    pub traps: Vec<Trap>,
     #[serde(rename = "teleporter")]
     #[serde(default)]
     pub teleporters: Vec<Teleporter>,
     #[serde(rename = "text")]
     #[serde(default)]
     pub texts: Vec<LevelText>,
+    #[serde(rename = "enemy")]
+    #[serde(default)]
+    pub enemies: Vec<LevelEnemy>,
+    #[serde(rename = "collectible")]
+    #[serde(default)]
+    pub collectibles: Vec<LevelCollectible>,
+    #[serde(rename = "door")]
+    #[serde(default)]
+    pub doors: Vec<LevelDoor>,
+    #[serde(rename = "trigger")]
+    #[serde(default)]
+    pub triggers: Vec<LevelTrigger>,
+    #[serde(rename = "crate")]
+    #[serde(default)]
+    pub crates: Vec<LevelCrate>,
+    #[serde(rename = "switch")]
+    #[serde(default)]
+    pub switches: Vec<LevelSwitch>,
+    #[serde(default)]
+    pub water: Vec<LevelWater>,
+    #[serde(rename = "ladder")]
+    #[serde(default)]
+    pub ladders: Vec<LevelLadder>,
+    #[serde(default)]
+    pub music: Option<String>,
+    #[serde(default)]
+    pub script: Option<String>,
+    #[serde(rename = "background")]
+    #[serde(default)]
+    pub backgrounds: Vec<Background>,
+}
+
+impl Level {
+    /// Checks what the level file format can't express, e.g. that every key has a name.
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, collectible) in self.collectibles.iter().enumerate() {
+            if collectible.kind == CollectibleKind::Key && collectible.name.is_empty() {
+                return Err(format!("key {} doesn't have a name", index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts the grid units of the level file into pixels.
+    pub fn scale(&mut self, size: f32) {
+        self.size.height *= size;
+        self.size.width *= size;
+        self.start.x *= size;
+        self.start.y *= size;
+        let scale_platform = |p: &mut Platform| {
+            p.x *= size;
+            p.y *= size;
+            p.width *= size;
+            p.height *= size;
+        };
+        self.platforms.iter_mut().for_each(scale_platform);
+        self.slopes.iter_mut().for_each(|s| {
+            s.x *= size;
+            s.width *= size;
+            s.left *= size;
+            s.right *= size;
+            s.height *= size;
+        });
+        self.traps.iter_mut().for_each(|t| {
+            t.x *= size;
+            t.y *= size;
+            t.width *= size;
+            t.height *= size;
+        });
+        self.teleporters.iter_mut().for_each(|p| {
+            p.x *= size;
+            p.y *= size;
+            p.width *= size;
+            p.height *= size;
+        });
+        self.texts.iter_mut().for_each(|t| {
+            t.x *= size;
+            t.y *= size;
+        });
+        self.doors.iter_mut().for_each(|d| {
+            d.x *= size;
+            d.y *= size;
+            d.width *= size;
+            d.height *= size;
+        });
+        self.triggers.iter_mut().for_each(|t| {
+            t.x *= size;
+            t.y *= size;
+            t.width *= size;
+            t.height *= size;
+        });
+        self.crates.iter_mut().for_each(|c| {
+            c.x *= size;
+            c.y *= size;
+            c.width *= size;
+            c.height *= size;
+        });
+        self.switches.iter_mut().for_each(|s| {
+            s.x *= size;
+            s.y *= size;
+            s.width *= size;
+        });
+        self.water.iter_mut().for_each(|w| {
+            w.x *= size;
+            w.y *= size;
+            w.width *= size;
+            w.height *= size;
+        });
+        self.ladders.iter_mut().for_each(|l| {
+            l.x *= size;
+            l.y *= size;
+            l.width *= size;
+            l.height *= size;
+        });
+        self.collectibles.iter_mut().for_each(|c| {
+            c.x *= size;
+            c.y *= size;
+        });
+        self.enemies.iter_mut().for_each(|e| {
+            e.x *= size;
+            e.y *= size;
+            e.width *= size;
+            e.height *= size;
+            e.chase_radius *= size;
+            e.patrol.iter_mut().for_each(|p| *p *= size);
+        });
+    }
 }
 
 impl Default for Level {
@@ -223,28 +982,51 @@ impl Default for Level {
                     y: 2.0,
                     width: 4.0,
                     height: 1.0,
+                    name: None,
+                    material: None,
+                    collision: LevelCollision::default(),
                 },
                 Platform {
                     x: 6.0,
                     y: 8.0,
                     width: 4.0,
                     height: 1.0,
+                    name: None,
+                    material: None,
+                    collision: LevelCollision::default(),
                 },
                 Platform {
                     x: 12.0,
                     y: 14.0,
                     width: 4.0,
                     height: 1.0,
+                    name: None,
+                    material: None,
+                    collision: LevelCollision::default(),
                 },
             ],
-            traps: vec![Platform {
+            slopes: vec![],
+            traps: vec![Trap {
                 x: 4.0,
                 y: 6.0,
                 width: 4.0,
                 height: 1.0,
+                damage: 1,
+                collision: LevelCollision::default(),
             }],
             teleporters: vec![],
             texts: vec![],
+            enemies: vec![],
+            collectibles: vec![],
+            doors: vec![],
+            triggers: vec![],
+            crates: vec![],
+            switches: vec![],
+            water: vec![],
+            ladders: vec![],
+            music: None,
+            script: None,
+            backgrounds: vec![],
         }
     }
 }
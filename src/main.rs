@@ -8,13 +8,13 @@ use std::path;
 
 use ggez::conf::{WindowMode, WindowSetup};
 use ggez::event::{KeyCode, KeyMods};
-use ggez::graphics::{Color, Mesh, MeshBuilder, Text};
 use ggez::*;
-use itertools::Itertools;
 
 use common::*;
-use components::*;
 
+mod audio;
+mod background;
+mod bot;
 mod camera;
 mod common;
 mod components;
@@ -22,219 +22,37 @@ mod config;
 mod controls;
 #[path = "../generated/default_levels.rs"]
 mod default_levels;
+mod enemy;
+mod events;
 mod game_state;
+mod generator;
+mod health;
+mod ladder;
 mod level;
+mod particles;
 mod physics;
+mod reachability;
+mod save;
+mod scenes;
+mod script;
+mod speedrun;
+mod stats;
+#[cfg(test)]
+mod tests;
+mod trigger;
+mod water;
 
 impl ggez::event::EventHandler for GameState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
-        while timer::check_update_time(ctx, DESIRED_FPS) {
-            self.tick += 1;
-
-            if self.change_level.is_some() {
-                let target = self.change_level.clone().unwrap();
-                self.change_level = None;
-                self.change_level(ctx, target)?;
-                break;
-            }
-
-            if self.restart_level {
-                self.restart_level = false;
-                self.restart_level(ctx)?;
-                break;
-            }
-
-            self.do_movement(ctx)?;
-            self.apply_physics(ctx)?;
-            self.move_camera(ctx)?;
-            self.collision_detection(ctx)?;
-            self.reset_pressed_state();
-        }
-
-        Ok(())
+        self.update_scene(ctx)
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         graphics::clear(ctx, [1.0, 1.0, 1.0, 1.0].into());
 
-        for (_id, (BoundingBox(bbox), mesh, _z_order)) in &mut self
-            .world
-            .query::<(&BoundingBox, &Mesh, &ZOrder)>()
-            .iter()
-            .sorted_by_key(|(_id, (_pos, _mesh, z_order))| -z_order.0)
-        // sort by z-order, descending
-        {
-            graphics::draw(
-                ctx,
-                &*mesh,
-                (relative_point(
-                    self.camera.center,
-                    Point2::new(bbox.x, bbox.y),
-                ),),
-            )?;
-        }
-
-        for (_id, (pos, mesh, _z_order)) in &mut self
-            .world
-            .query::<(&Position, &Mesh, &ZOrder)>()
-            .iter()
-            .sorted_by_key(|(_id, (_pos, _mesh, z_order))| -z_order.0)
-        // sort by z-order, descending
-        {
-            graphics::draw(ctx, &*mesh, (relative_point(self.camera.center, pos.0),))?;
-        }
-
-        for (_id, (pos, text, col)) in
-            &mut self.world.query::<(&Position, &TextContainer, &Color)>()
-        {
-            graphics::draw(
-                ctx,
-                &Text::new(
-                    graphics::TextFragment::new(text.value.clone())
-                        .color(*col)
-                        .scale(graphics::Scale::uniform(text.size)),
-                ),
-                (relative_point(self.camera.center, pos.0),),
-            )?;
-        }
-
-        let ac_text = if self.config.player.allow_air_control {
-            String::from("AirControl: on")
-        } else {
-            String::from("AirControl: off")
-        };
-
-        graphics::draw(
-            ctx,
-            &Text::new(
-                graphics::TextFragment::new(ac_text)
-                    .color(Color::from_rgb(0, 0, 0))
-                    .scale(graphics::Scale::uniform(30.0)),
-            ),
-            (Point2::new(5.0, 2.0),),
-        )?;
-
-        if self.config.debug.draw_bounds {
-            let mut mb = MeshBuilder::new();
-            // bounds with pos
-            for (_id, (pos, BoundingBox(bbox))) in
-                &mut self.world.query::<(&Position, &BoundingBox)>()
-            {
-                const BBOX_WIDTH: f32 = 2.0;
-                const HALF_BBOX_WIDTH: f32 = BBOX_WIDTH / 2.0;
-                mb.line(
-                    &[
-                        Point2::new(
-                            pos.0.x + bbox.x + HALF_BBOX_WIDTH,
-                            pos.0.y + bbox.y + HALF_BBOX_WIDTH,
-                        ),
-                        Point2::new(
-                            pos.0.x + bbox.x + bbox.w - HALF_BBOX_WIDTH,
-                            pos.0.y + bbox.y + HALF_BBOX_WIDTH,
-                        ),
-                    ],
-                    BBOX_WIDTH,
-                    Color::from_rgb(255, 0, 0),
-                )?;
-                mb.line(
-                    &[
-                        Point2::new(
-                            pos.0.x + bbox.x + HALF_BBOX_WIDTH,
-                            pos.0.y + bbox.y + bbox.h - HALF_BBOX_WIDTH,
-                        ),
-                        Point2::new(
-                            pos.0.x + bbox.x + bbox.w - HALF_BBOX_WIDTH,
-                            pos.0.y + bbox.y + bbox.h - HALF_BBOX_WIDTH,
-                        ),
-                    ],
-                    BBOX_WIDTH,
-                    Color::from_rgb(255, 0, 0),
-                )?;
-                mb.line(
-                    &[
-                        Point2::new(
-                            pos.0.x + bbox.x + HALF_BBOX_WIDTH,
-                            pos.0.y + bbox.y + HALF_BBOX_WIDTH,
-                        ),
-                        Point2::new(
-                            pos.0.x + bbox.x + HALF_BBOX_WIDTH,
-                            pos.0.y + bbox.y + bbox.h - HALF_BBOX_WIDTH,
-                        ),
-                    ],
-                    BBOX_WIDTH,
-                    Color::from_rgb(255, 0, 0),
-                )?;
-                mb.line(
-                    &[
-                        Point2::new(
-                            pos.0.x + bbox.x + bbox.w - HALF_BBOX_WIDTH,
-                            pos.0.y + bbox.y + HALF_BBOX_WIDTH,
-                        ),
-                        Point2::new(
-                            pos.0.x + bbox.x + bbox.w - HALF_BBOX_WIDTH,
-                            pos.0.y + bbox.y + bbox.h - HALF_BBOX_WIDTH,
-                        ),
-                    ],
-                    BBOX_WIDTH,
-                    Color::from_rgb(255, 0, 0),
-                )?;
-            }
-
-            // bounds without pos
-            for (_id, BoundingBox(bbox)) in
-                &mut self.world.query::<Without<Position, &BoundingBox>>()
-            {
-                const BBOX_WIDTH: f32 = 2.0;
-                const HALF_BBOX_WIDTH: f32 = BBOX_WIDTH / 2.0;
-                mb.line(
-                    &[
-                        Point2::new(bbox.x + HALF_BBOX_WIDTH, bbox.y + HALF_BBOX_WIDTH),
-                        Point2::new(bbox.x + bbox.w - HALF_BBOX_WIDTH, bbox.y + HALF_BBOX_WIDTH),
-                    ],
-                    BBOX_WIDTH,
-                    Color::from_rgb(255, 0, 0),
-                )?;
-                mb.line(
-                    &[
-                        Point2::new(bbox.x + HALF_BBOX_WIDTH, bbox.y + bbox.h - HALF_BBOX_WIDTH),
-                        Point2::new(
-                            bbox.x + bbox.w - HALF_BBOX_WIDTH,
-                            bbox.y + bbox.h - HALF_BBOX_WIDTH,
-                        ),
-                    ],
-                    BBOX_WIDTH,
-                    Color::from_rgb(255, 0, 0),
-                )?;
-                mb.line(
-                    &[
-                        Point2::new(bbox.x + HALF_BBOX_WIDTH, bbox.y + HALF_BBOX_WIDTH),
-                        Point2::new(bbox.x + HALF_BBOX_WIDTH, bbox.y + bbox.h - HALF_BBOX_WIDTH),
-                    ],
-                    BBOX_WIDTH,
-                    Color::from_rgb(255, 0, 0),
-                )?;
-                mb.line(
-                    &[
-                        Point2::new(bbox.x + bbox.w - HALF_BBOX_WIDTH, bbox.y + HALF_BBOX_WIDTH),
-                        Point2::new(
-                            bbox.x + bbox.w - HALF_BBOX_WIDTH,
-                            bbox.y + bbox.h - HALF_BBOX_WIDTH,
-                        ),
-                    ],
-                    BBOX_WIDTH,
-                    Color::from_rgb(255, 0, 0),
-                )?;
-            }
+        self.draw_scene(ctx)?;
 
-            let mesh = mb.build(ctx)?;
-            graphics::draw(
-                ctx,
-                &mesh,
-                (relative_point(self.camera.center, Point2::new(0.0, 0.0)),),
-            )?;
-        }
-
-        if self.tick % 50 == 0 {
+        if timer::ticks(ctx).is_multiple_of(50) {
             graphics::set_window_title(ctx, &format!("{:.0} FPS", timer::fps(ctx)));
         }
 
@@ -249,15 +67,22 @@ impl ggez::event::EventHandler for GameState {
         keymods: KeyMods,
         repeat: bool,
     ) {
-        self.map_key_down_event(ctx, keycode, keymods, repeat);
+        self.key_down_scene(ctx, keycode, keymods, repeat);
     }
 
     fn key_up_event(&mut self, ctx: &mut Context, keycode: KeyCode, keymods: KeyMods) {
-        self.map_key_up_event(ctx, keycode, keymods);
+        self.key_up_scene(ctx, keycode, keymods);
     }
 }
 
 fn main() -> GameResult {
+    let args = env::args().collect::<Vec<_>>();
+    if let [_, flag, level, target] = args.as_slice() {
+        if flag == "--solve" {
+            return bot::solve(level, target);
+        }
+    }
+
     let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
         let mut path = path::PathBuf::from(manifest_dir);
         path.push("resources");
@@ -0,0 +1,245 @@
+use std::f32::consts::PI;
+
+use ggez::graphics;
+use ggez::graphics::{Color, DrawMode, MeshBuilder, Rect};
+use rand::Rng;
+
+use crate::common::*;
+use crate::components::*;
+use crate::events::GameEvent;
+
+pub struct Particle {
+    pub position: Point2,
+    pub velocity: Vector2,
+    pub age: u32,
+    pub lifetime: u32,
+    pub gravity: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub start_size: f32,
+    pub end_size: f32,
+}
+
+impl Particle {
+    fn progress(&self) -> f32 {
+        self.age as f32 / self.lifetime as f32
+    }
+
+    fn color(&self) -> Color {
+        let t = self.progress();
+        Color::new(
+            lerp(self.start_color.r, self.end_color.r, t),
+            lerp(self.start_color.g, self.end_color.g, t),
+            lerp(self.start_color.b, self.end_color.b, t),
+            lerp(self.start_color.a, self.end_color.a, t),
+        )
+    }
+
+    fn size(&self) -> f32 {
+        lerp(self.start_size, self.end_size, self.progress())
+    }
+}
+
+#[inline(always)]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+impl ParticleEmitter {
+    pub fn dust() -> Self {
+        ParticleEmitter {
+            rate: 0.5,
+            lifetime: 20,
+            speed: 0.8,
+            angle: -PI / 2.0,
+            spread: PI / 3.0,
+            gravity: 0.05,
+            start_color: Color::from_rgba(120, 100, 80, 200),
+            end_color: Color::from_rgba(120, 100, 80, 0),
+            start_size: 4.0,
+            end_size: 1.0,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn landing() -> Self {
+        ParticleEmitter {
+            speed: 1.5,
+            spread: PI / 2.0,
+            ..ParticleEmitter::dust()
+        }
+    }
+
+    pub fn death() -> Self {
+        ParticleEmitter {
+            rate: 0.0,
+            lifetime: 45,
+            speed: 4.0,
+            angle: 0.0,
+            spread: PI,
+            gravity: 0.15,
+            start_color: Color::from_rgb(0, 0, 255),
+            end_color: Color::from_rgba(255, 0, 0, 0),
+            start_size: 6.0,
+            end_size: 2.0,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn teleport() -> Self {
+        ParticleEmitter {
+            rate: 0.0,
+            lifetime: 40,
+            speed: 2.5,
+            angle: 0.0,
+            spread: PI,
+            gravity: -0.02,
+            start_color: Color::from_rgb(0, 255, 0),
+            end_color: Color::from_rgba(255, 255, 255, 0),
+            start_size: 5.0,
+            end_size: 0.5,
+            accumulator: 0.0,
+        }
+    }
+}
+
+impl GameState {
+    pub fn emit_particles(&mut self, emitter: &ParticleEmitter, position: Point2, count: usize) {
+        if !self.config.particles.enabled {
+            return;
+        }
+
+        let mut rng = rand::thread_rng();
+        let count = count.min(
+            self.config
+                .particles
+                .max_particles
+                .saturating_sub(self.particles.len()),
+        );
+
+        for _ in 0..count {
+            let angle = emitter.angle + rng.gen_range(-emitter.spread, emitter.spread);
+            let speed = emitter.speed * rng.gen_range(0.5, 1.0);
+            self.particles.push(Particle {
+                position,
+                velocity: Vector2::new(angle.cos(), angle.sin()) * speed,
+                age: 0,
+                lifetime: emitter.lifetime,
+                gravity: emitter.gravity,
+                start_color: emitter.start_color,
+                end_color: emitter.end_color,
+                start_size: emitter.start_size,
+                end_size: emitter.end_size,
+            });
+        }
+    }
+
+    #[inline(always)]
+    pub fn update_particles(&mut self) -> GameResult<()> {
+        let mut emitted = vec![];
+        for (_id, (emitter, position, velocity, grounded)) in
+            &mut self
+                .world
+                .query::<(&mut ParticleEmitter, &Position, &Velocity, &Grounded)>()
+        {
+            if grounded.0 <= 0 || velocity.0.x.abs() < self.config.particles.dust_speed {
+                emitter.accumulator = 0.0;
+                continue;
+            }
+
+            emitter.accumulator += emitter.rate;
+            let count = emitter.accumulator as usize;
+            emitter.accumulator -= count as f32;
+
+            if count > 0 {
+                let mut emitter = emitter.clone();
+                // kick the dust up behind the entity
+                emitter.angle = if velocity.0.x > 0.0 {
+                    -PI * 0.75
+                } else {
+                    -PI * 0.25
+                };
+                emitted.push((emitter, position.0, count));
+            }
+        }
+
+        for (emitter, position, count) in emitted {
+            self.emit_particles(&emitter, position, count);
+        }
+
+        for particle in &mut self.particles {
+            particle.velocity.y += particle.gravity;
+            particle.position += particle.velocity;
+            particle.age += 1;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+
+        Ok(())
+    }
+
+    pub fn handle_particle_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Landed(id) => {
+                let position = self.world.get::<Position>(*id).map(|p| p.0);
+                if let Ok(position) = position {
+                    self.emit_particles(&ParticleEmitter::landing(), position, 8);
+                }
+            }
+            GameEvent::PlayerDied => {
+                if let Some(position) = self.player_center() {
+                    self.emit_particles(&ParticleEmitter::death(), position, 40);
+                }
+            }
+            GameEvent::LevelChanged { from, to } if from != to => {
+                self.particles.clear();
+                if let Some(position) = self.player_center() {
+                    self.emit_particles(&ParticleEmitter::teleport(), position, 30);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn player_center(&self) -> Option<Point2> {
+        self.world
+            .query::<(&Position, &BoundingBox, &Player)>()
+            .iter()
+            .map(|(_id, (position, BoundingBox(bbox), _))| {
+                Point2::new(
+                    position.0.x + bbox.x + bbox.w / 2.0,
+                    position.0.y + bbox.y + bbox.h / 2.0,
+                )
+            })
+            .next()
+    }
+
+    /// All particles are batched into a single mesh in world coordinates.
+    pub fn draw_particles(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if self.particles.is_empty() {
+            return Ok(());
+        }
+
+        let mut mb = MeshBuilder::new();
+        for particle in &self.particles {
+            let size = particle.size();
+            mb.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    particle.position.x - size / 2.0,
+                    particle.position.y - size / 2.0,
+                    size,
+                    size,
+                ),
+                particle.color(),
+            );
+        }
+
+        let mesh = mb.build(ctx)?;
+        graphics::draw(
+            ctx,
+            &mesh,
+            (relative_point(self.camera.center, Point2::new(0.0, 0.0)),),
+        )
+    }
+}
@@ -1,7 +1,10 @@
+use std::collections::HashSet;
+
 use ggez::GameResult;
 
 use crate::common::*;
 use crate::components::*;
+use crate::events::GameEvent;
 
 pub trait SafeNormalization {
     fn normalize_safe(&self) -> Self;
@@ -23,6 +26,50 @@ fn limit(v: f32, max: f32) -> f32 {
     }
 }
 
+/// Slower landings on bouncy surfaces come to rest instead of bouncing forever.
+const MIN_BOUNCE_VELOCITY: f32 = 1.0;
+
+/// Stops a downward movement onto a surface or bounces off it. Returns whether the entity stays
+/// on the surface.
+fn land(velocity: &mut Velocity, material: &Material) -> bool {
+    if material.restitution > 0.0 && velocity.0.y > MIN_BOUNCE_VELOCITY {
+        velocity.0.y *= -material.restitution;
+        false
+    } else {
+        velocity.0.y = 0.0;
+        true
+    }
+}
+
+/// Shares the horizontal momentum of an entity moving into a pushable body with it, otherwise
+/// stops the entity. Returns the new velocity of the body if it is pushed.
+fn push(
+    velocity: &mut Velocity,
+    mass: Option<&Mass>,
+    body: Option<(f32, f32)>,
+    direction: f32,
+) -> Option<f32> {
+    match (mass, body) {
+        (Some(mass), Some((body_velocity, body_mass))) if velocity.0.x * direction > 0.0 => {
+            velocity.0.x =
+                (velocity.0.x * mass.0 + body_velocity * body_mass) / (mass.0 + body_mass);
+            Some(velocity.0.x)
+        }
+        _ => {
+            velocity.0.x = 0.0;
+            None
+        }
+    }
+}
+
+/// Which side of an entity touches a solid.
+enum Contact {
+    Floor,
+    Ceiling,
+    Left,
+    Right,
+}
+
 pub trait PhysicsHelper {
     fn apply_force(&mut self, force: &Vector2, mass: f32) -> &mut Self;
 
@@ -40,25 +87,53 @@ pub fn apply_gravity(v: &mut Vector2, force: &Vector2) {
 
 impl GameState {
     #[inline(always)]
-    pub fn apply_physics(&mut self, _ctx: &mut Context) -> GameResult<()> {
-        for (_id, (acceleration, velocity, mass, grounded)) in
-            &mut self
-                .world
-                .query::<(&mut Acceleration, &mut Velocity, &Mass, &Grounded)>()
+    pub fn apply_physics(&mut self) -> GameResult<()> {
+        for (_id, (acceleration, velocity, mass, grounded, ground, pushable)) in
+            &mut self.world.query::<Without<
+                Climbing,
+                Without<
+                    Swimming,
+                    (
+                        &mut Acceleration,
+                        &mut Velocity,
+                        &Mass,
+                        &Grounded,
+                        Option<&Ground>,
+                        Option<&Pushable>,
+                    ),
+                >,
+            >>()
         {
             if grounded.0 > 0 {
-                let mut friction = velocity.0;
-                friction *= -1.0;
-                friction = friction.normalize_safe();
-                friction *= self.config.physics.friction * self.config.physics.normal_force;
-                acceleration.apply_force(&friction, mass.0);
+                let ground = ground.copied().unwrap_or_default();
+                let tangent = Vector2::new(-ground.normal.y, ground.normal.x);
+                // friction acts along the surface, pulling towards the speed of conveyors, and is
+                // weaker the steeper the surface is
+                let along = (velocity.0 - tangent * ground.material.conveyor).dot(&tangent);
+                if along != 0.0 {
+                    let strength = (self.config.physics.friction
+                        * ground.material.friction
+                        * pushable.map_or(1.0, |pushable| pushable.friction)
+                        * self.config.physics.normal_force
+                        * -ground.normal.y)
+                        .min(along.abs() * mass.0);
+                    let friction = tangent * -along.signum() * strength;
+                    acceleration.apply_force(&friction, mass.0);
+                }
             }
         }
 
-        for (_id, (acceleration, velocity, position, gravity)) in
-            &mut self
-                .world
-                .query::<(&mut Acceleration, &mut Velocity, &mut Position, &Gravity)>()
+        let water = &self.config.physics.water;
+        for (_id, (acceleration, velocity, position, gravity, grounded, ground, swimming)) in
+            &mut self.world.query::<(
+                &mut Acceleration,
+                &mut Velocity,
+                &mut Position,
+                &Gravity,
+                Option<&Grounded>,
+                Option<&Ground>,
+                Option<&Swimming>,
+            )>()
         {
             acceleration.apply_gravity(&gravity.0);
 
@@ -67,9 +142,22 @@ impl GameState {
 
             acceleration.0 *= 0.0;
 
-            // limit velocity
-            velocity.0.x = limit(velocity.0.x, self.config.physics.max_horizontal_velocity);
-            velocity.0.y = limit(velocity.0.y, self.config.physics.max_vertical_velocity);
+            // limit velocity, water slows down everything in it instead of friction
+            if swimming.is_some() {
+                velocity.0 *= 1.0 - water.drag;
+                velocity.0.x = limit(velocity.0.x, water.max_horizontal_velocity);
+                velocity.0.y = limit(velocity.0.y, water.max_vertical_velocity);
+            } else {
+                let speed = match (grounded, ground) {
+                    (Some(grounded), Some(ground)) if grounded.0 > 0 => ground.material.speed,
+                    _ => 1.0,
+                };
+                velocity.0.x = limit(
+                    velocity.0.x,
+                    self.config.physics.max_horizontal_velocity * speed,
+                );
+                velocity.0.y = limit(velocity.0.y, self.config.physics.max_vertical_velocity);
+            }
 
             if velocity.0.x.abs() < self.config.physics.movement_deadzone {
                 velocity.0.x = 0.0;
@@ -87,31 +175,146 @@ impl GameState {
     }
 
     #[inline(always)]
-    pub fn collision_detection(&mut self, _ctx: &mut Context) -> GameResult<()> {
+    pub fn collision_detection(&mut self) -> GameResult<()> {
+        let flat = Vector2::new(0.0, -1.0);
         let mut grounded_entities = vec![];
-        for (id, (velocity, position, &BoundingBox(bbox))) in
-            &mut self
-                .world
-                .query::<(&mut Velocity, &mut Position, &BoundingBox)>()
-        {
-            let mut bbox = bbox;
+        let mut fallen_entities = vec![];
+        let mut picked = vec![];
+        let mut opened = vec![];
+        let mut pushes = vec![];
+        let mut carried = vec![];
+        let mut overlapping_triggers = HashSet::new();
+        let mut pressed_switches = HashSet::new();
+
+        let mut colliders =
+            self.world
+                .query::<Without<
+                    Position,
+                    Without<Slope, (&BoundingBox, Option<&Collider>, Option<&Material>)>,
+                >>()
+                .iter()
+                .map(|(id, (BoundingBox(bbox), collider, material))| {
+                    (
+                        id,
+                        *bbox,
+                        collider.copied().unwrap_or_default(),
+                        material.copied().unwrap_or_default(),
+                        None,
+                    )
+                })
+                .collect::<Vec<_>>();
+        // pushables block where they are at the start of the collision pass, entities standing on
+        // them are carried along like on a conveyor
+        colliders.extend(
+            self.world
+                .query::<(
+                    &Position,
+                    &BoundingBox,
+                    &Velocity,
+                    &Mass,
+                    Option<&Collider>,
+                    &Pushable,
+                )>()
+                .iter()
+                .map(
+                    |(id, (position, &BoundingBox(mut bbox), velocity, mass, collider, _))| {
+                        bbox.translate(Vector2::new(position.0.x, position.0.y));
+                        let material = Material {
+                            conveyor: velocity.0.x,
+                            ..Material::default()
+                        };
+                        (
+                            id,
+                            bbox,
+                            collider.copied().unwrap_or_default(),
+                            material,
+                            Some((velocity.0.x, mass.0)),
+                        )
+                    },
+                ),
+        );
+        let slopes = self
+            .world
+            .query::<Without<
+                Position,
+                (&BoundingBox, &Slope, Option<&Collider>, Option<&Material>),
+            >>()
+            .iter()
+            .map(|(_id, (BoundingBox(bbox), slope, collider, material))| {
+                (
+                    *bbox,
+                    *slope,
+                    collider.copied().unwrap_or_default(),
+                    material.copied().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for (
+            id,
+            (velocity, position, &BoundingBox(relative), collider, player, grounded, ground, mass),
+        ) in &mut self.world.query::<(
+            &mut Velocity,
+            &mut Position,
+            &BoundingBox,
+            Option<&Collider>,
+            Option<&Player>,
+            Option<&Grounded>,
+            Option<&Ground>,
+            Option<&Mass>,
+        )>() {
+            let mut bbox = relative;
             bbox.translate(Vector2::new(position.0.x, position.0.y));
-
-            for (other, BoundingBox(other_bbox)) in self
-                .world
-                .query::<&BoundingBox>()
+            let collider = collider.copied().unwrap_or_default();
+            let was_grounded = grounded.is_some_and(|g| g.0 > 0);
+            // entities walking off the top of a slope step up onto the platform it leads to, which
+            // they overlap by up to half their width at the seam
+            let on_slope = ground.is_some_and(|ground| ground.normal.x != 0.0);
+            let step_height = if was_grounded && on_slope {
+                bbox.w / 2.0
+            } else {
+                0.0
+            };
+
+            for &(other, other_bbox, other_collider, material, body) in colliders
                 .iter()
-                .filter(|(other, _)| id != *other)
-                .filter(|(_, BoundingBox(other))| other.overlaps(&bbox))
+                .filter(|(other, _, _, _, _)| id != *other)
+                .filter(|(_, other, _, _, _)| other.overlaps(&bbox))
             {
-                if let Ok(mut q) = self.world.query_one::<&Death>(other) {
-                    if q.get().is_some() {
-                        self.restart_level = true;
+                let interacts = collider.interacts(&other_collider);
+
+                if let Ok(mut q) = self.world.query_one::<&Collectible>(other) {
+                    if let Some(collectible) = q.get() {
+                        if player.is_some() && interacts {
+                            picked.push((
+                                other,
+                                collectible.index,
+                                collectible.kind,
+                                collectible.name.clone(),
+                            ));
+                        }
                     }
                 }
 
-                if let Ok(mut q) = self.world.query_one::<&TeleportTo>(other) {
-                    self.change_level = q.get().map(|tele| tele.0.clone());
+                if let Ok(mut q) = self.world.query_one::<&Door>(other) {
+                    if let Some(Door(key)) = q.get() {
+                        if player.is_some() && self.keys.contains(key) {
+                            opened.push(other);
+                            continue;
+                        }
+                    }
+                }
+
+                if interacts && self.world.get::<Trigger>(other).is_ok() {
+                    overlapping_triggers.insert((other, id));
+                }
+
+                if interacts && self.world.get::<Switch>(other).is_ok() {
+                    pressed_switches.insert(other);
+                }
+
+                if !other_collider.blocks(&collider) {
+                    continue;
                 }
 
                 let bbox_left = bbox.left();
@@ -130,63 +333,154 @@ impl GameState {
                 let tl = other_bbox.contains(top_left);
                 let tr = other_bbox.contains(top_right);
 
-                if bl && br {
-                    velocity.0.y = 0.0;
-                    position.0.y = other_bbox.top();
-                    grounded_entities.push(id);
+                let contact = if bl && br {
+                    Contact::Floor
                 } else if tl && tr {
-                    velocity.0.y /= 2.0;
-                    position.0.y = other_bbox.bottom() + self.config.player.size;
+                    Contact::Ceiling
                 } else if tl && bl {
-                    velocity.0.x = 0.0;
-                    position.0.x = other_bbox.right() + (self.config.player.size / 2.0);
+                    Contact::Left
                 } else if tr && br {
-                    velocity.0.x = 0.0;
-                    position.0.x = other_bbox.left() - (self.config.player.size / 2.0);
+                    Contact::Right
+                } else if bl || br {
+                    let distance_y = bbox_bottom - other_bbox.top();
+                    let distance_x = if bl {
+                        bbox_left - other_bbox.right()
+                    } else {
+                        bbox_right - other_bbox.left()
+                    };
+                    if distance_y.abs() <= distance_x.abs().max(step_height) {
+                        Contact::Floor
+                    } else if bl {
+                        Contact::Left
+                    } else {
+                        Contact::Right
+                    }
+                } else if tl || tr {
+                    let distance_y = bbox_top - other_bbox.bottom();
+                    let distance_x = if tl {
+                        bbox_left - other_bbox.right()
+                    } else {
+                        bbox_right - other_bbox.left()
+                    };
+                    if distance_y.abs() <= distance_x.abs() {
+                        Contact::Ceiling
+                    } else if tl {
+                        Contact::Left
+                    } else {
+                        Contact::Right
+                    }
                 } else {
-                    // TODO: simplify this mess
-                    if bl {
-                        let distance_y = bbox_bottom - other_bbox.top();
-                        let distance_x = bbox_left - other_bbox.right();
-                        if distance_y.abs() <= distance_x.abs() {
-                            velocity.0.y = 0.0;
-                            position.0.y = other_bbox.top();
-                            grounded_entities.push(id);
-                        } else {
-                            velocity.0.x = 0.0;
-                            position.0.x = other_bbox.right() + (self.config.player.size / 2.0);
+                    continue;
+                };
+
+                // positions are corrected so that the entity's own bounding box touches the other one
+                match contact {
+                    Contact::Floor => {
+                        position.0.y = other_bbox.top() - relative.bottom();
+                        if body.is_some() {
+                            carried.push((id, other, other_bbox.top()));
                         }
-                    } else if br {
-                        let distance_y = bbox_bottom - other_bbox.top();
-                        let distance_x = bbox_right - other_bbox.left();
-                        if distance_y.abs() <= distance_x.abs() {
-                            velocity.0.y = 0.0;
-                            position.0.y = other_bbox.top();
-                            grounded_entities.push(id);
-                        } else {
-                            velocity.0.x = 0.0;
-                            position.0.x = other_bbox.left() - (self.config.player.size / 2.0);
+                        if land(velocity, &material) {
+                            grounded_entities.push((
+                                id,
+                                Ground {
+                                    normal: flat,
+                                    material,
+                                },
+                            ));
                         }
-                    } else if tl {
-                        let distance_y = bbox_top - other_bbox.bottom();
-                        let distance_x = bbox_left - other_bbox.right();
-                        if distance_y.abs() <= distance_x.abs() {
-                            velocity.0.y /= 2.0;
-                            position.0.y = other_bbox.bottom() + self.config.player.size;
-                        } else {
-                            velocity.0.x = 0.0;
-                            position.0.x = other_bbox.right() + (self.config.player.size / 2.0);
+                    }
+                    Contact::Ceiling => {
+                        velocity.0.y /= 2.0;
+                        position.0.y = other_bbox.bottom() - relative.top();
+                    }
+                    Contact::Left => {
+                        position.0.x = other_bbox.right() - relative.left();
+                        if let Some(pushed) = push(velocity, mass, body, -1.0) {
+                            pushes.push((other, pushed));
+                        }
+                    }
+                    Contact::Right => {
+                        position.0.x = other_bbox.left() - relative.right();
+                        if let Some(pushed) = push(velocity, mass, body, 1.0) {
+                            pushes.push((other, pushed));
                         }
-                    } else if tr {
-                        let distance_y = bbox_top - other_bbox.bottom();
-                        let distance_x = bbox_right - other_bbox.left();
-                        if distance_y.abs() <= distance_x.abs() {
-                            velocity.0.y /= 2.0;
-                            position.0.y = other_bbox.bottom() + self.config.player.size;
+                    }
+                }
+            }
+
+            for (slope_bbox, slope, slope_collider, material) in &slopes {
+                if !slope_collider.blocks(&collider) {
+                    continue;
+                }
+
+                let mut bbox = relative;
+                bbox.translate(Vector2::new(position.0.x, position.0.y));
+                let center = bbox.x + bbox.w / 2.0;
+                if center < slope_bbox.left() || center > slope_bbox.right() {
+                    // only a corner is over the slope, it can only block from the side
+                    let edge = slope.surface_at(center);
+                    if slope_bbox.overlaps(&bbox)
+                        && bbox.bottom() > edge + 1.0
+                        && bbox.top() < slope_bbox.bottom()
+                    {
+                        velocity.0.x = 0.0;
+                        if center < slope_bbox.left() {
+                            position.0.x -= bbox.right() - slope_bbox.left();
                         } else {
-                            velocity.0.x = 0.0;
-                            position.0.x = other_bbox.left() - (self.config.player.size / 2.0);
+                            position.0.x += slope_bbox.right() - bbox.left();
                         }
+                    } else if was_grounded
+                        && on_slope
+                        && velocity.0.y >= 0.0
+                        && bbox.right() > slope_bbox.left()
+                        && bbox.left() < slope_bbox.right()
+                    {
+                        // walking off the lower end keeps the entity on the ground it leads to
+                        let gap = edge - bbox.bottom();
+                        let step = velocity.0.x.abs() * slope.gradient().abs() + 1.0;
+                        if gap > 0.0 && gap <= step {
+                            position.0.y += gap;
+                            velocity.0.y = 0.0;
+                        }
+                    }
+                    continue;
+                }
+
+                if bbox.bottom() > slope_bbox.bottom() {
+                    if bbox.top() < slope_bbox.bottom() {
+                        velocity.0.y /= 2.0;
+                        position.0.y += slope_bbox.bottom() - bbox.top();
+                    }
+                    continue;
+                }
+
+                let surface = slope.surface_at(center);
+                let depth = bbox.bottom() - surface;
+                // how far the surface can be from the entity's feet after one tick, including the
+                // part of the entity hanging over the higher side of the slope
+                let step = (velocity.0.x.abs() + bbox.w / 2.0) * slope.gradient().abs()
+                    + velocity.0.y.abs()
+                    + 1.0;
+
+                if depth > step {
+                    velocity.0.x = 0.0;
+                    if center < slope_bbox.x + slope_bbox.w / 2.0 {
+                        position.0.x -= bbox.right() - slope_bbox.left();
+                    } else {
+                        position.0.x += slope_bbox.right() - bbox.left();
+                    }
+                } else if depth >= 0.0 || (was_grounded && velocity.0.y >= 0.0 && -depth <= step) {
+                    // snapping down while grounded keeps the entity from bouncing downhill
+                    position.0.y -= depth;
+                    if velocity.0.y >= 0.0 && land(velocity, material) {
+                        grounded_entities.push((
+                            id,
+                            Ground {
+                                normal: slope.normal(),
+                                material: *material,
+                            },
+                        ));
                     }
                 }
             }
@@ -211,24 +505,97 @@ impl GameState {
             if position.0.y >= max_y {
                 position.0.y = max_y;
                 velocity.0.y = 0.0;
-                self.restart_level = true;
+                if player.is_some() {
+                    self.events.publish(GameEvent::PlayerDied);
+                } else {
+                    fallen_entities.push(id);
+                }
             } else if position.0.y <= min_y {
                 position.0.y = min_y;
                 velocity.0.y = 0.0;
             }
         }
 
+        // entities standing on a pushable follow it when it is pushed out of the ground later in
+        // the pass
+        for (id, body, top) in carried {
+            let settled = match (
+                self.world.get::<Position>(body),
+                self.world.get::<BoundingBox>(body),
+            ) {
+                (Ok(position), Ok(bbox)) => position.0.y + bbox.0.top(),
+                _ => continue,
+            };
+            if let Ok(mut position) = self.world.get_mut::<Position>(id) {
+                position.0.y += settled - top;
+            }
+        }
+
         for (id, grounded) in &mut self.world.query::<&mut Grounded>() {
-            if grounded_entities.contains(&id) {
+            if grounded_entities
+                .iter()
+                .any(|(grounded, _)| *grounded == id)
+            {
+                if grounded.0 <= 0 {
+                    self.events.publish(GameEvent::Landed(id));
+                }
                 grounded.0 = COYOTE_TIME_IN_TICKS;
             } else if grounded.0 >= 0 {
                 grounded.0 -= 1;
             }
         }
 
-        for id in grounded_entities {
+        for (id, ground) in grounded_entities {
             let _ = self.world.insert_one(id, Grounded(COYOTE_TIME_IN_TICKS));
+            let _ = self.world.insert_one(id, ground);
+        }
+
+        for (id, pushed) in pushes {
+            if let Ok(mut velocity) = self.world.get_mut::<Velocity>(id) {
+                velocity.0.x = pushed;
+            }
+        }
+
+        for (id, switch) in &mut self.world.query::<&Switch>() {
+            if pressed_switches.contains(&id) {
+                self.flags.insert(switch.flag.clone());
+            } else {
+                self.flags.remove(&switch.flag);
+            }
+        }
+
+        for id in fallen_entities {
+            let _ = self.world.despawn(id);
+        }
+
+        for (id, index, kind, name) in picked {
+            if self.world.despawn(id).is_ok() {
+                self.events.publish(GameEvent::CollectiblePicked {
+                    level: self.current_level.clone(),
+                    index,
+                    kind,
+                    name,
+                });
+            }
+        }
+
+        for id in opened {
+            let _ = self.world.despawn(id);
+        }
+
+        for &(trigger, entity) in overlapping_triggers.difference(&self.trigger_overlaps) {
+            self.events
+                .publish(GameEvent::TriggerEntered { trigger, entity });
+        }
+        for &(trigger, entity) in overlapping_triggers.intersection(&self.trigger_overlaps) {
+            self.events
+                .publish(GameEvent::TriggerStayed { trigger, entity });
+        }
+        for &(trigger, entity) in self.trigger_overlaps.difference(&overlapping_triggers) {
+            self.events
+                .publish(GameEvent::TriggerExited { trigger, entity });
         }
+        self.trigger_overlaps = overlapping_triggers;
 
         Ok(())
     }
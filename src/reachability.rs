@@ -0,0 +1,318 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use ggez::graphics::Rect;
+
+use crate::common::*;
+use crate::config::Config;
+use crate::level::Level;
+
+/// Number of take off speeds tried between standing still and the fastest possible run up.
+const SPEED_SAMPLES: usize = 10;
+
+/// Jumps are cut off after this many ticks, in case they never come down.
+const MAX_JUMP_TICKS: usize = 10_000;
+
+/// A model of the player's jump, derived from the config.
+///
+/// Mirrors `do_movement` and `apply_physics`: the jump impulse grows with the current speed, and
+/// holding the jump button reduces gravity. There is no air control, so speed is kept in the air.
+#[derive(Debug, Clone)]
+pub struct JumpModel {
+    /// A moderate run speed used when a conservative estimate is needed.
+    pub run_speed: f32,
+    pub max_run_speed: f32,
+    /// Acceleration on the ground, after friction.
+    pub ground_acceleration: f32,
+    pub jump_impulse: f32,
+    pub gravity: f32,
+    pub max_fall_speed: f32,
+    pub size: f32,
+}
+
+impl JumpModel {
+    /// Fails if jumps would never come down again.
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let gravity = config.physics.gravity * config.player.float_modifier;
+        if gravity <= 0.0 || gravity.is_nan() {
+            return Err(format!(
+                "gravity while floating is {}, it has to be positive",
+                gravity
+            ));
+        }
+
+        let mass = config.player.mass;
+        Ok(JumpModel {
+            run_speed: config.physics.max_horizontal_velocity / 2.0,
+            max_run_speed: config.physics.max_horizontal_velocity,
+            ground_acceleration: (config.player.acceleration
+                - config.physics.friction * config.physics.normal_force)
+                / mass,
+            jump_impulse: config.player.jump_acceleration / mass,
+            gravity,
+            max_fall_speed: config.physics.max_vertical_velocity,
+            size: config.player.size,
+        })
+    }
+
+    pub fn jump_velocity(&self, speed: f32) -> f32 {
+        (self.jump_impulse * (1.0 + speed / 30.0)).min(self.max_fall_speed)
+    }
+
+    /// Fastest speed that can be reached on a platform of the given width in pixels.
+    pub fn run_up_speed(&self, width: f32) -> f32 {
+        if self.ground_acceleration <= 0.0 {
+            return 0.0;
+        }
+        (2.0 * self.ground_acceleration * width)
+            .sqrt()
+            .min(self.max_run_speed)
+    }
+
+    /// Offsets of the player's feet per tick, with y pointing up, until it fell `depth` pixels.
+    pub fn trajectory(&self, speed: f32, depth: f32) -> Vec<(f32, f32)> {
+        let mut points = vec![];
+        let (mut x, mut y) = (0.0, 0.0);
+        let mut velocity = self.jump_velocity(speed);
+
+        while y > -depth && points.len() < MAX_JUMP_TICKS {
+            velocity = (velocity - self.gravity).max(-self.max_fall_speed);
+            x += speed;
+            y += velocity;
+            points.push((x, y));
+        }
+        points
+    }
+
+    /// Highest point of a standing jump in grid units.
+    pub fn max_rise(&self) -> f32 {
+        let mut y = 0.0;
+        let mut velocity = self.jump_velocity(0.0);
+        for _ in 0..MAX_JUMP_TICKS {
+            if velocity <= 0.0 {
+                break;
+            }
+            velocity -= self.gravity;
+            y += velocity;
+        }
+        y / self.size
+    }
+
+    /// Horizontal distance in grid units covered at `run_speed` before falling back down to
+    /// `rise` grid units above the take off point, or `None` if that height can't be reached.
+    pub fn reach(&self, rise: f32) -> Option<f32> {
+        let rise = rise * self.size;
+        let mut apex = 0.0f32;
+
+        for (x, y) in self.trajectory(self.run_speed, (-rise).max(0.0) + self.size) {
+            apex = apex.max(y);
+            if y <= rise && y < apex {
+                return if apex >= rise {
+                    Some(x / self.size)
+                } else {
+                    None
+                };
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ReachabilityReport {
+    /// Set if the player doesn't land on any platform after spawning.
+    pub start_falls: bool,
+    pub unreachable_platforms: Vec<usize>,
+    pub unreachable_teleporters: Vec<usize>,
+}
+
+impl ReachabilityReport {
+    /// Human readable warnings with positions in grid units, like in the level file.
+    pub fn warnings(&self, level: &Level, size: f32) -> Vec<String> {
+        let mut warnings = vec![];
+        if self.start_falls {
+            warnings.push("the player doesn't land on a platform after spawning".to_string());
+        }
+        for &index in &self.unreachable_platforms {
+            let platform = &level.platforms[index];
+            warnings.push(format!(
+                "platform {} at ({}, {}) is unreachable",
+                index,
+                platform.x / size,
+                platform.y / size
+            ));
+        }
+        for &index in &self.unreachable_teleporters {
+            let teleporter = &level.teleporters[index];
+            warnings.push(format!(
+                "teleporter {} to {} at ({}, {}) is unreachable",
+                index,
+                teleporter.target,
+                teleporter.x / size,
+                teleporter.y / size
+            ));
+        }
+        warnings
+    }
+}
+
+fn speeds(model: &JumpModel, surface: &Rect) -> impl Iterator<Item = f32> {
+    let max = model.run_up_speed(surface.w);
+    (0..=SPEED_SAMPLES).map(move |i| max * i as f32 / SPEED_SAMPLES as f32)
+}
+
+/// Whether the span `[left, right]`, moved by `dx` to either side, overlaps `[min, max]`.
+#[inline(always)]
+fn overlaps_either_way(left: f32, right: f32, dx: f32, min: f32, max: f32) -> bool {
+    (left + dx <= max && right + dx >= min) || (left - dx <= max && right - dx >= min)
+}
+
+/// Whether a jump from anywhere on `from` lands on top of `to`.
+pub fn can_land(model: &JumpModel, from: &Rect, to: &Rect, depth: f32) -> bool {
+    let half = model.size / 2.0;
+    let rise = from.y - to.y;
+
+    speeds(model, from).any(|speed| {
+        let mut previous = 0.0;
+        model.trajectory(speed, depth).into_iter().any(|(dx, y)| {
+            let lands = y <= rise && previous > rise;
+            previous = y;
+            lands
+                && overlaps_either_way(
+                    from.left(),
+                    from.right(),
+                    dx,
+                    to.left() - half,
+                    to.right() + half,
+                )
+        })
+    })
+}
+
+/// Whether the player's bounding box touches `target` at any point of a jump from `from`.
+pub fn can_touch(model: &JumpModel, from: &Rect, target: &Rect, depth: f32) -> bool {
+    let half = model.size / 2.0;
+
+    speeds(model, from).any(|speed| {
+        model.trajectory(speed, depth).into_iter().any(|(dx, y)| {
+            let bottom = from.y - y;
+            let top = bottom - model.size;
+            top <= target.bottom()
+                && bottom >= target.top()
+                && overlaps_either_way(
+                    from.left() - half,
+                    from.right() + half,
+                    dx,
+                    target.left(),
+                    target.right(),
+                )
+        })
+    })
+}
+
+/// Builds a graph of which platforms can be jumped to from which, starting from the platform the
+/// player lands on after spawning.
+///
+/// This is an estimate to warn about level design mistakes, not a guarantee either way. Ceilings
+/// and hazards are ignored, but run ups are limited to the platform the jump starts from and the
+/// jump impulse only takes the horizontal speed into account. Surface materials, water and ladders
+/// aren't modelled either, so platforms only reachable by bouncing, riding a conveyor, swimming or
+/// climbing are reported as unreachable.
+///
+/// The level has to be scaled to pixels already.
+pub fn analyze(level: &Level, model: &JumpModel) -> ReachabilityReport {
+    let height = level.size.height;
+    let mut surfaces = level
+        .platforms
+        .iter()
+        .map(|p| Rect::new(p.x, height - p.y, p.width + 1.0, p.height + 1.0))
+        .collect::<Vec<_>>();
+    // slopes are approximated by stairs, one step per grid cell
+    for slope in &level.slopes {
+        let steps = (slope.width / model.size).ceil().max(1.0) as usize;
+        let step = slope.width / steps as f32;
+        for i in 0..steps {
+            let t = (i as f32 + 0.5) / steps as f32;
+            let y = slope.left + (slope.right - slope.left) * t;
+            surfaces.push(Rect::new(
+                slope.x + i as f32 * step,
+                height - y,
+                step + 1.0,
+                1.0,
+            ));
+        }
+    }
+    let teleporters = level
+        .teleporters
+        .iter()
+        .map(|t| Rect::new(t.x, height - t.y, t.width + 1.0, t.height + 1.0))
+        .collect::<Vec<_>>();
+
+    let start_x = level.start.x;
+    let start_y = height - level.start.y - 1.0;
+    let start = surfaces
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.left() <= start_x && s.right() >= start_x && s.top() >= start_y)
+        .min_by(|(_, a), (_, b)| a.top().partial_cmp(&b.top()).unwrap_or(Ordering::Equal))
+        .map(|(index, _)| index);
+
+    let mut report = ReachabilityReport::default();
+    let mut reachable = vec![false; surfaces.len()];
+    let mut queue = VecDeque::new();
+
+    match start {
+        Some(index) => {
+            reachable[index] = true;
+            queue.push_back(index);
+        }
+        None => report.start_falls = true,
+    }
+
+    while let Some(from) = queue.pop_front() {
+        for to in 0..surfaces.len() {
+            if !reachable[to] && can_land(model, &surfaces[from], &surfaces[to], height) {
+                reachable[to] = true;
+                queue.push_back(to);
+            }
+        }
+    }
+
+    report.unreachable_platforms = (0..level.platforms.len())
+        .filter(|&i| !reachable[i])
+        .collect();
+    report.unreachable_teleporters = teleporters
+        .iter()
+        .enumerate()
+        .filter(|(_, teleporter)| {
+            !(0..surfaces.len())
+                .filter(|&i| reachable[i])
+                .any(|i| can_touch(model, &surfaces[i], teleporter, height))
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    report
+}
+
+impl GameState {
+    /// Prints the warnings of the reachability analysis for every level.
+    pub fn warn_unreachable(&self) {
+        let model = match JumpModel::from_config(&self.config) {
+            Ok(model) => model,
+            Err(e) => {
+                println!("levels can't be analyzed: {}", e);
+                return;
+            }
+        };
+
+        let mut names = self.levels.keys().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let level = &self.levels[name];
+            for warning in analyze(level, &model).warnings(level, self.config.player.size) {
+                println!("level {}: {}", name, warning);
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use ggez::filesystem;
+
+use crate::common::*;
+use crate::level::CollectibleKind;
+use crate::stats::RunStats;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Checkpoint {
+    pub level: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SaveGame {
+    #[serde(default)]
+    pub unlocked_levels: BTreeSet<String>,
+    #[serde(default)]
+    pub collectibles: BTreeMap<String, BTreeSet<usize>>,
+    #[serde(default)]
+    pub best_times: BTreeMap<String, usize>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<Checkpoint>,
+}
+
+/// Progress restored from the save game, it replaces the current run on the next level change.
+#[derive(Debug, Default)]
+pub struct ContinuedRun {
+    pub stats: RunStats,
+    pub keys: BTreeSet<String>,
+}
+
+impl SaveGame {
+    /// The save file lives in the user data directory, or in `resources/` if that can't be created.
+    pub fn path(ctx: &Context) -> PathBuf {
+        let dir = filesystem::user_data_dir(ctx).to_path_buf();
+        if std::fs::create_dir_all(&dir).is_ok() {
+            dir.join("save.toml")
+        } else {
+            PathBuf::from("resources/save.toml")
+        }
+    }
+
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|data| match toml::from_str::<SaveGame>(&data) {
+                Ok(save) => Some(save),
+                Err(e) => {
+                    println!(
+                        "failed to parse save file ({:?}) with the following error: {}",
+                        path, e
+                    );
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    /// Writes to a temporary file first, so a crash never leaves a half written save behind.
+    pub fn write(&self, path: &Path) {
+        let data = match toml::to_string(self) {
+            Ok(data) => data,
+            Err(e) => {
+                println!("failed to serialize save game: {}", e);
+                return;
+            }
+        };
+
+        let tmp = path.with_extension("toml.tmp");
+        if let Err(e) = std::fs::write(&tmp, data).and_then(|_| std::fs::rename(&tmp, path)) {
+            println!(
+                "failed to write save file ({:?}) with the following error: {}",
+                path, e
+            );
+        }
+    }
+}
+
+impl GameState {
+    /// Records the progress when the player leaves `previous_level` for another level.
+    pub fn update_save(&mut self, previous_level: &str) {
+        self.save.unlocked_levels.insert(self.current_level.clone());
+
+        for (level, stats) in &self.stats.levels {
+            self.save
+                .collectibles
+                .entry(level.clone())
+                .or_default()
+                .extend(stats.collected.iter().copied());
+        }
+
+        if let Some(stats) = self.stats.levels.get(previous_level) {
+            let best = self
+                .save
+                .best_times
+                .entry(previous_level.to_string())
+                .or_insert(stats.ticks);
+            *best = (*best).min(stats.ticks);
+        }
+
+        if let Some(level) = self.levels.get(&self.current_level) {
+            self.save.checkpoint = Some(Checkpoint {
+                level: self.current_level.clone(),
+                x: level.start.x,
+                y: level.size.height - level.start.y - 1.0,
+            });
+        }
+
+        if let Some(path) = &self.save_path {
+            self.save.write(path);
+        }
+    }
+
+    /// Restores the collected items of the save game and continues at the last checkpoint.
+    pub fn continue_game(&mut self) {
+        let checkpoint = match self.save.checkpoint.clone() {
+            Some(checkpoint) if self.levels.contains_key(&checkpoint.level) => checkpoint,
+            _ => return,
+        };
+
+        let mut run = ContinuedRun::default();
+        for (level, indices) in &self.save.collectibles {
+            let collectibles = match self.levels.get(level) {
+                Some(level) => &level.collectibles,
+                None => continue,
+            };
+
+            for &index in indices {
+                if let Some(collectible) = collectibles.get(index) {
+                    run.stats
+                        .record_pickup(level, index, collectible.kind.value());
+                    if collectible.kind == CollectibleKind::Key {
+                        run.keys.insert(collectible.name.clone());
+                    }
+                }
+            }
+        }
+
+        // applied by the level change, so it isn't lost if that starts a new run
+        self.continued_run = Some(run);
+        self.spawn_position = Some(Point2::new(checkpoint.x, checkpoint.y));
+        self.play_level(checkpoint.level);
+    }
+}
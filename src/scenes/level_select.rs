@@ -0,0 +1,253 @@
+use std::collections::{HashMap, VecDeque};
+
+use ggez::graphics;
+use ggez::graphics::{Color, DrawMode, MeshBuilder, Rect, Text};
+
+use crate::common::*;
+use crate::scenes::*;
+use crate::stats::format_ticks;
+
+const NODE_WIDTH: f32 = 160.0;
+const NODE_HEIGHT: f32 = 60.0;
+const COLUMN_WIDTH: f32 = 220.0;
+const ROW_HEIGHT: f32 = 110.0;
+const ORIGIN: (f32, f32) = (80.0, 160.0);
+
+#[derive(Debug)]
+pub struct OverworldNode {
+    pub level: String,
+    pub column: usize,
+    pub row: usize,
+    pub unlocked: bool,
+}
+
+impl OverworldNode {
+    pub fn bounds(&self) -> Rect {
+        Rect::new(
+            ORIGIN.0 + self.column as f32 * COLUMN_WIDTH,
+            ORIGIN.1 + self.row as f32 * ROW_HEIGHT,
+            NODE_WIDTH,
+            NODE_HEIGHT,
+        )
+    }
+}
+
+/// Level graph derived from the teleporters of every level.
+///
+/// Levels are placed in columns by their distance from `start`; levels that can't be reached
+/// through teleporters end up in an extra column at the end.
+#[derive(Debug)]
+pub struct Overworld {
+    pub nodes: Vec<OverworldNode>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl GameState {
+    pub fn overworld(&self) -> Overworld {
+        let mut depths: HashMap<&str, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        if self.levels.contains_key("start") {
+            depths.insert("start", 0);
+            queue.push_back("start");
+        }
+
+        while let Some(level) = queue.pop_front() {
+            let depth = depths[level];
+            let mut targets = self.levels[level]
+                .teleporters
+                .iter()
+                .map(|t| t.target.as_str())
+                .filter(|target| self.levels.contains_key(*target))
+                .collect::<Vec<_>>();
+            targets.sort();
+            for target in targets {
+                if !depths.contains_key(target) {
+                    depths.insert(target, depth + 1);
+                    queue.push_back(target);
+                }
+            }
+        }
+
+        let unreachable_column = depths.values().max().map_or(0, |max| max + 1);
+        let mut names = self.levels.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort_by_key(|name| {
+            (
+                depths.get(name).copied().unwrap_or(unreachable_column),
+                *name,
+            )
+        });
+
+        let mut rows: HashMap<usize, usize> = HashMap::new();
+        let nodes = names
+            .iter()
+            .map(|name| {
+                let column = depths.get(name).copied().unwrap_or(unreachable_column);
+                let row = rows.entry(column).or_insert(0);
+                *row += 1;
+                OverworldNode {
+                    level: name.to_string(),
+                    column,
+                    row: *row - 1,
+                    unlocked: *name == "start" || self.save.unlocked_levels.contains(*name),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let index = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (*name, i))
+            .collect::<HashMap<_, _>>();
+
+        let mut edges = vec![];
+        for (from, name) in names.iter().enumerate() {
+            for teleporter in &self.levels[*name].teleporters {
+                if let Some(&to) = index.get(teleporter.target.as_str()) {
+                    if !edges.contains(&(from, to)) {
+                        edges.push((from, to));
+                    }
+                }
+            }
+        }
+
+        Overworld { nodes, edges }
+    }
+
+    pub fn draw_level_select(&mut self, ctx: &mut Context, selected: usize) -> GameResult<()> {
+        let overworld = self.overworld();
+
+        graphics::draw(
+            ctx,
+            &Text::new(
+                graphics::TextFragment::new("Level Select")
+                    .color(graphics::BLACK)
+                    .scale(graphics::Scale::uniform(60.0)),
+            ),
+            (Point2::new(ORIGIN.0, 40.0),),
+        )?;
+
+        let mut mb = MeshBuilder::new();
+        for &(from, to) in overworld.edges.iter().filter(|(from, to)| from != to) {
+            let from = overworld.nodes[from].bounds();
+            let to = overworld.nodes[to].bounds();
+            mb.line(
+                &[
+                    Point2::new(from.x + from.w / 2.0, from.y + from.h / 2.0),
+                    Point2::new(to.x + to.w / 2.0, to.y + to.h / 2.0),
+                ],
+                3.0,
+                Color::from_rgb(120, 120, 120),
+            )?;
+        }
+
+        for (index, node) in overworld.nodes.iter().enumerate() {
+            let bounds = node.bounds();
+            if index == selected {
+                let mut border = bounds;
+                border.x -= 4.0;
+                border.y -= 4.0;
+                border.w += 8.0;
+                border.h += 8.0;
+                mb.rectangle(DrawMode::fill(), border, Color::from_rgb(255, 215, 0));
+            }
+
+            let color = if node.unlocked {
+                Color::from_rgb(0, 0, 160)
+            } else {
+                Color::from_rgb(140, 140, 140)
+            };
+            mb.rectangle(DrawMode::fill(), bounds, color);
+        }
+
+        let mesh = mb.build(ctx)?;
+        graphics::draw(ctx, &mesh, (Point2::new(0.0, 0.0),))?;
+
+        for node in &overworld.nodes {
+            let bounds = node.bounds();
+            let label = if node.unlocked {
+                node.level.clone()
+            } else {
+                format!("{} (locked)", node.level)
+            };
+
+            graphics::draw(
+                ctx,
+                &Text::new(
+                    graphics::TextFragment::new(label)
+                        .color(graphics::WHITE)
+                        .scale(graphics::Scale::uniform(20.0)),
+                ),
+                (Point2::new(bounds.x + 6.0, bounds.y + 6.0),),
+            )?;
+
+            let collected = self
+                .save
+                .collectibles
+                .get(&node.level)
+                .map_or(0, |collected| collected.len());
+            let total = self.levels[&node.level].collectibles.len();
+            let time = self
+                .save
+                .best_times
+                .get(&node.level)
+                .map_or_else(|| "--:--.---".to_string(), |ticks| format_ticks(*ticks));
+
+            graphics::draw(
+                ctx,
+                &Text::new(
+                    graphics::TextFragment::new(format!("{}/{} {}", collected, total, time))
+                        .color(graphics::WHITE)
+                        .scale(graphics::Scale::uniform(16.0)),
+                ),
+                (Point2::new(bounds.x + 6.0, bounds.y + 34.0),),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn menu_level_select(&mut self, selected: usize, input: MenuInput) {
+        let overworld = self.overworld();
+        let current = match overworld.nodes.get(selected) {
+            Some(node) => node,
+            None => {
+                self.select(0);
+                return;
+            }
+        };
+
+        match input {
+            MenuInput::Select => {
+                if current.unlocked {
+                    self.play_level(current.level.clone());
+                }
+            }
+            MenuInput::Back => {
+                self.scenes.pop();
+            }
+            MenuInput::Left | MenuInput::Right => {
+                let column = if input == MenuInput::Left {
+                    current.column.checked_sub(1)
+                } else {
+                    Some(current.column + 1)
+                };
+
+                // jump to the closest row in the neighbouring column
+                let target = column.and_then(|column| {
+                    overworld
+                        .nodes
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, node)| node.column == column)
+                        .min_by_key(|(_, node)| (node.row as isize - current.row as isize).abs())
+                        .map(|(index, _)| index)
+                });
+
+                if let Some(index) = target {
+                    self.select(index);
+                }
+            }
+            _ => self.select(navigate(selected, overworld.nodes.len(), &input)),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics;
+use ggez::graphics::{Color, DrawMode, Rect, Text};
+use ggez::timer;
+
+use crate::common::*;
+use crate::events::GameEvent;
+
+mod level_select;
+mod options;
+mod paused;
+mod playing;
+mod results;
+mod title;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scene {
+    Title { selected: usize },
+    LevelSelect { selected: usize },
+    Playing,
+    Paused { selected: usize },
+    Options { selected: usize },
+    Results,
+}
+
+impl Scene {
+    /// Overlays are drawn on top of the scene below them.
+    pub fn is_overlay(&self) -> bool {
+        matches!(
+            self,
+            Scene::Paused { .. } | Scene::Options { .. } | Scene::Results
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+pub fn menu_input(keycode: KeyCode) -> Option<MenuInput> {
+    match keycode {
+        KeyCode::W | KeyCode::Up => Some(MenuInput::Up),
+        KeyCode::S | KeyCode::Down => Some(MenuInput::Down),
+        KeyCode::A | KeyCode::Left => Some(MenuInput::Left),
+        KeyCode::D | KeyCode::Right => Some(MenuInput::Right),
+        KeyCode::Return | KeyCode::Space => Some(MenuInput::Select),
+        KeyCode::Escape => Some(MenuInput::Back),
+        _ => None,
+    }
+}
+
+pub fn navigate(selected: usize, count: usize, input: &MenuInput) -> usize {
+    if count == 0 {
+        return 0;
+    }
+
+    match input {
+        MenuInput::Up => (selected + count - 1) % count,
+        MenuInput::Down => (selected + 1) % count,
+        _ => selected,
+    }
+}
+
+pub fn draw_overlay(ctx: &mut Context) -> GameResult<()> {
+    let overlay = graphics::Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, 0.0, WIDTH, HEIGHT),
+        Color::new(0.0, 0.0, 0.0, 0.6),
+    )?;
+    graphics::draw(ctx, &overlay, (Point2::new(0.0, 0.0),))
+}
+
+pub fn draw_menu(
+    ctx: &mut Context,
+    title: &str,
+    items: &[String],
+    selected: usize,
+    color: Color,
+) -> GameResult<()> {
+    graphics::draw(
+        ctx,
+        &Text::new(
+            graphics::TextFragment::new(title)
+                .color(color)
+                .scale(graphics::Scale::uniform(60.0)),
+        ),
+        (Point2::new(MIDDLE_X - 200.0, 120.0),),
+    )?;
+
+    for (index, item) in items.iter().enumerate() {
+        let text = if index == selected {
+            format!("> {}", item)
+        } else {
+            format!("  {}", item)
+        };
+
+        graphics::draw(
+            ctx,
+            &Text::new(
+                graphics::TextFragment::new(text)
+                    .color(color)
+                    .scale(graphics::Scale::uniform(30.0)),
+            ),
+            (Point2::new(MIDDLE_X - 200.0, 220.0 + index as f32 * 40.0),),
+        )?;
+    }
+
+    Ok(())
+}
+
+impl GameState {
+    pub fn update_scene(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.update_audio(ctx)?;
+
+        match self.scenes.last() {
+            Some(Scene::Playing) => self.update_playing(ctx),
+            _ => {
+                // don't let the fixed timestep pile up while the game isn't running
+                while timer::check_update_time(ctx, DESIRED_FPS) {}
+                Ok(())
+            }
+        }
+    }
+
+    pub fn draw_scene(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let base = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0);
+
+        // copied, since drawing a scene borrows the state mutably
+        let scenes = self.scenes[base..].to_vec();
+        for scene in scenes {
+            match scene {
+                Scene::Title { selected } => self.draw_title(ctx, selected)?,
+                Scene::LevelSelect { selected } => self.draw_level_select(ctx, selected)?,
+                Scene::Playing => self.draw_playing(ctx)?,
+                Scene::Paused { selected } => self.draw_paused(ctx, selected)?,
+                Scene::Options { selected } => self.draw_options(ctx, selected)?,
+                Scene::Results => self.draw_results(ctx)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn key_down_scene(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        keymods: KeyMods,
+        repeat: bool,
+    ) {
+        let scene = match self.scenes.last() {
+            Some(scene) => *scene,
+            None => return,
+        };
+
+        if scene == Scene::Playing {
+            self.key_down_playing(ctx, keycode, keymods, repeat);
+            return;
+        }
+
+        if repeat {
+            return;
+        }
+
+        if let Some(input) = menu_input(keycode) {
+            match scene {
+                Scene::Title { selected } => self.menu_title(ctx, selected, input),
+                Scene::LevelSelect { selected } => self.menu_level_select(selected, input),
+                Scene::Paused { selected } => self.menu_paused(selected, input),
+                Scene::Options { selected } => self.menu_options(selected, input),
+                Scene::Results => self.menu_results(input),
+                Scene::Playing => (),
+            }
+        }
+    }
+
+    pub fn key_up_scene(&mut self, ctx: &mut Context, keycode: KeyCode, keymods: KeyMods) {
+        if let Some(Scene::Playing) = self.scenes.last() {
+            self.map_key_up_event(ctx, keycode, keymods);
+        }
+    }
+
+    /// Changes the selected menu entry of the topmost scene.
+    pub fn select(&mut self, index: usize) {
+        match self.scenes.last_mut() {
+            Some(Scene::Title { selected })
+            | Some(Scene::LevelSelect { selected })
+            | Some(Scene::Paused { selected })
+            | Some(Scene::Options { selected }) => *selected = index,
+            _ => (),
+        }
+    }
+
+    /// Starts playing the given level, dropping everything above the title screen.
+    pub fn play_level(&mut self, level: String) {
+        self.events.publish(GameEvent::LevelChangeRequested(level));
+        self.controls = Controls::default();
+        self.scenes.truncate(1);
+        self.scenes.push(Scene::Playing);
+    }
+}
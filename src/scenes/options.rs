@@ -0,0 +1,79 @@
+use ggez::graphics;
+
+use crate::common::*;
+use crate::scenes::*;
+
+const VOLUME_STEP: f32 = 0.1;
+
+fn percent(value: f32) -> String {
+    format!("{:.0}%", value * 100.0)
+}
+
+fn adjust(volume: &mut f32, input: &MenuInput) {
+    match input {
+        MenuInput::Left => *volume = (*volume - VOLUME_STEP).max(0.0),
+        MenuInput::Right => *volume = (*volume + VOLUME_STEP).min(1.0),
+        _ => (),
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+impl GameState {
+    pub fn option_items(&self) -> Vec<String> {
+        vec![
+            format!(
+                "Air Control: {}",
+                on_off(self.config.player.allow_air_control)
+            ),
+            format!("Debug Bounds: {}", on_off(self.config.debug.draw_bounds)),
+            format!(
+                "Master Volume: {}",
+                percent(self.config.audio.master_volume)
+            ),
+            format!("Music Volume: {}", percent(self.config.audio.music_volume)),
+            format!("Sfx Volume: {}", percent(self.config.audio.sfx_volume)),
+            "Back".to_string(),
+        ]
+    }
+
+    pub fn draw_options(&mut self, ctx: &mut Context, selected: usize) -> GameResult<()> {
+        draw_overlay(ctx)?;
+        draw_menu(
+            ctx,
+            "Options",
+            &self.option_items(),
+            selected,
+            graphics::WHITE,
+        )
+    }
+
+    pub fn menu_options(&mut self, selected: usize, input: MenuInput) {
+        match input {
+            MenuInput::Select => match selected {
+                0 => self.config.player.allow_air_control = !self.config.player.allow_air_control,
+                1 => self.config.debug.draw_bounds = !self.config.debug.draw_bounds,
+                2..=4 => (),
+                _ => {
+                    self.scenes.pop();
+                }
+            },
+            MenuInput::Left | MenuInput::Right => match selected {
+                2 => adjust(&mut self.config.audio.master_volume, &input),
+                3 => adjust(&mut self.config.audio.music_volume, &input),
+                4 => adjust(&mut self.config.audio.sfx_volume, &input),
+                _ => (),
+            },
+            MenuInput::Back => {
+                self.scenes.pop();
+            }
+            _ => self.select(navigate(selected, self.option_items().len(), &input)),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use ggez::graphics;
+
+use crate::common::*;
+use crate::events::GameEvent;
+use crate::scenes::*;
+
+const PAUSED_ITEMS: [&str; 4] = ["Resume", "Restart Level", "Options", "Quit to Title"];
+
+impl GameState {
+    pub fn draw_paused(&mut self, ctx: &mut Context, selected: usize) -> GameResult<()> {
+        draw_overlay(ctx)?;
+        let items = PAUSED_ITEMS
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        draw_menu(ctx, "Paused", &items, selected, graphics::WHITE)
+    }
+
+    pub fn menu_paused(&mut self, selected: usize, input: MenuInput) {
+        match input {
+            MenuInput::Select => match selected {
+                0 => {
+                    self.scenes.pop();
+                }
+                1 => {
+                    self.events.publish(GameEvent::RestartRequested);
+                    self.scenes.pop();
+                }
+                2 => self.scenes.push(Scene::Options { selected: 0 }),
+                _ => {
+                    self.speedrun.reset();
+                    self.scenes.truncate(1);
+                }
+            },
+            MenuInput::Back => {
+                self.scenes.pop();
+            }
+            _ => self.select(navigate(selected, PAUSED_ITEMS.len(), &input)),
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics;
+use ggez::graphics::{Color, DrawMode, Mesh, MeshBuilder, Text};
+use ggez::timer;
+use itertools::Itertools;
+
+use crate::common::*;
+use crate::components::*;
+use crate::events::GameEvent;
+use crate::scenes::Scene;
+
+impl GameState {
+    pub fn update_playing(&mut self, ctx: &mut Context) -> GameResult<()> {
+        while timer::check_update_time(ctx, DESIRED_FPS) {
+            if !self.step()? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Advances the game by one tick. Doesn't need a window, so it can be driven by tests.
+    ///
+    /// Returns whether the next tick should run in the same frame.
+    pub fn step(&mut self) -> GameResult<bool> {
+        // requests published between ticks, e.g. by the menus, are handled instead of a tick
+        let next_level = match self.dispatch_events()? {
+            Some(level) => {
+                self.tick += 1;
+                Some(level)
+            }
+            None => self.advance()?,
+        };
+
+        if let Some(level) = next_level {
+            self.change_level(level)?;
+            self.process_events()?;
+            return Ok(false);
+        }
+
+        Ok(self.scenes.last() == Some(&Scene::Playing))
+    }
+
+    /// Runs the systems for one tick and handles their events. Returns the level a restart or
+    /// level change asked for without leaving the current one.
+    pub fn advance(&mut self) -> GameResult<Option<String>> {
+        self.tick += 1;
+
+        self.do_movement()?;
+        self.update_enemies()?;
+        self.update_water()?;
+        self.climb_ladders()?;
+        self.apply_physics()?;
+        self.move_camera()?;
+        self.collision_detection()?;
+        self.stand_on_ladders()?;
+        self.enemy_contact()?;
+        self.update_invulnerability()?;
+        self.update_script()?;
+        self.update_particles()?;
+        let next_level = self.dispatch_events()?;
+        self.reset_pressed_state();
+
+        Ok(next_level)
+    }
+
+    /// Creates the meshes of entities that only have a shape or polygon, e.g. the ones spawned
+    /// since the last frame.
+    fn build_meshes(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let mut meshes = vec![];
+        for (id, shape) in &mut self.world.query::<Without<Mesh, &Shape>>() {
+            meshes.push((
+                id,
+                Mesh::new_rectangle(ctx, DrawMode::fill(), shape.rect, shape.color)?,
+            ));
+        }
+        for (id, polygon) in &mut self.world.query::<Without<Mesh, &Polygon>>() {
+            meshes.push((
+                id,
+                Mesh::new_polygon(ctx, DrawMode::fill(), &polygon.points, polygon.color)?,
+            ));
+        }
+        for (id, mesh) in meshes {
+            let _ = self.world.insert_one(id, mesh);
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_scene_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::LevelChanged { from, to } if from != to && to == "end" => {
+                self.scenes.push(Scene::Results)
+            }
+            GameEvent::DebugToggled => {
+                self.config.debug.draw_bounds = !self.config.debug.draw_bounds;
+                self.config.debug.draw_grid = !self.config.debug.draw_grid;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn key_down_playing(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        keymods: KeyMods,
+        repeat: bool,
+    ) {
+        if keycode == KeyCode::Escape {
+            self.controls = Controls::default();
+            self.scenes.push(Scene::Paused { selected: 0 });
+        } else {
+            self.map_key_down_event(ctx, keycode, keymods, repeat);
+        }
+    }
+
+    pub fn draw_playing(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.build_meshes(ctx)?;
+        self.draw_backgrounds(ctx)?;
+
+        for (_id, (BoundingBox(bbox), mesh, _z_order)) in &mut self
+            .world
+            .query::<(&BoundingBox, &Mesh, &ZOrder)>()
+            .iter()
+            .sorted_by_key(|(_id, (_pos, _mesh, z_order))| -z_order.0)
+        // sort by z-order, descending
+        {
+            graphics::draw(
+                ctx,
+                mesh,
+                (relative_point(
+                    self.camera.center,
+                    Point2::new(bbox.x, bbox.y),
+                ),),
+            )?;
+        }
+
+        for (_id, (pos, mesh, _z_order, invulnerable)) in &mut self
+            .world
+            .query::<(&Position, &Mesh, &ZOrder, Option<&Invulnerable>)>()
+            .iter()
+            .sorted_by_key(|(_id, (_pos, _mesh, z_order, _))| -z_order.0)
+        // sort by z-order, descending
+        {
+            // flash while invulnerable
+            if invulnerable.is_some_and(|i| (i.0 / 5) % 2 == 0) {
+                continue;
+            }
+            graphics::draw(ctx, mesh, (relative_point(self.camera.center, pos.0),))?;
+        }
+
+        self.draw_particles(ctx)?;
+
+        for (_id, (pos, text, col)) in
+            &mut self.world.query::<(&Position, &TextContainer, &Color)>()
+        {
+            graphics::draw(
+                ctx,
+                &Text::new(
+                    graphics::TextFragment::new(text.value.clone())
+                        .color(*col)
+                        .scale(graphics::Scale::uniform(text.size)),
+                ),
+                (relative_point(self.camera.center, pos.0),),
+            )?;
+        }
+
+        let ac_text = if self.config.player.allow_air_control {
+            String::from("AirControl: on")
+        } else {
+            String::from("AirControl: off")
+        };
+
+        graphics::draw(
+            ctx,
+            &Text::new(
+                graphics::TextFragment::new(ac_text)
+                    .color(Color::from_rgb(0, 0, 0))
+                    .scale(graphics::Scale::uniform(30.0)),
+            ),
+            (Point2::new(5.0, 2.0),),
+        )?;
+
+        graphics::draw(
+            ctx,
+            &Text::new(
+                graphics::TextFragment::new(format!("Score: {}", self.stats.score))
+                    .color(Color::from_rgb(0, 0, 0))
+                    .scale(graphics::Scale::uniform(30.0)),
+            ),
+            (Point2::new(5.0, 66.0),),
+        )?;
+
+        if !self.keys.is_empty() {
+            graphics::draw(
+                ctx,
+                &Text::new(
+                    graphics::TextFragment::new(format!("Keys: {}", self.keys.iter().join(", ")))
+                        .color(Color::from_rgb(0, 0, 0))
+                        .scale(graphics::Scale::uniform(30.0)),
+                ),
+                (Point2::new(5.0, 98.0),),
+            )?;
+        }
+
+        for (_id, (health, _)) in &mut self.world.query::<(&Health, &Player)>() {
+            let health_text = format!("Health: {}/{}", health.current, health.max);
+            graphics::draw(
+                ctx,
+                &Text::new(
+                    graphics::TextFragment::new(health_text)
+                        .color(Color::from_rgb(0, 0, 0))
+                        .scale(graphics::Scale::uniform(30.0)),
+                ),
+                (Point2::new(5.0, 34.0),),
+            )?;
+        }
+
+        let max_air = self.config.physics.water.air_ticks;
+        for (_id, (air, _)) in &mut self.world.query::<(&Air, &Player)>() {
+            if air.0 < max_air {
+                graphics::draw(
+                    ctx,
+                    &Text::new(
+                        graphics::TextFragment::new(format!("Air: {}%", air.0 * 100 / max_air))
+                            .color(Color::from_rgb(0, 0, 0))
+                            .scale(graphics::Scale::uniform(30.0)),
+                    ),
+                    (Point2::new(5.0, 130.0),),
+                )?;
+            }
+        }
+
+        self.draw_speedrun(ctx)?;
+
+        if let Some((message, until)) = &self.message {
+            if self.tick < *until {
+                let text = Text::new(
+                    graphics::TextFragment::new(message.as_str())
+                        .color(Color::from_rgb(0, 0, 0))
+                        .scale(graphics::Scale::uniform(30.0)),
+                );
+                let x = MIDDLE_X - text.width(ctx) as f32 / 2.0;
+                graphics::draw(ctx, &text, (Point2::new(x, HEIGHT - 60.0),))?;
+            }
+        }
+
+        if self.config.debug.draw_bounds {
+            let mut mb = MeshBuilder::new();
+            // bounds with pos
+            for (_id, (pos, BoundingBox(bbox))) in
+                &mut self.world.query::<(&Position, &BoundingBox)>()
+            {
+                const BBOX_WIDTH: f32 = 2.0;
+                const HALF_BBOX_WIDTH: f32 = BBOX_WIDTH / 2.0;
+                mb.line(
+                    &[
+                        Point2::new(
+                            pos.0.x + bbox.x + HALF_BBOX_WIDTH,
+                            pos.0.y + bbox.y + HALF_BBOX_WIDTH,
+                        ),
+                        Point2::new(
+                            pos.0.x + bbox.x + bbox.w - HALF_BBOX_WIDTH,
+                            pos.0.y + bbox.y + HALF_BBOX_WIDTH,
+                        ),
+                    ],
+                    BBOX_WIDTH,
+                    Color::from_rgb(255, 0, 0),
+                )?;
+                mb.line(
+                    &[
+                        Point2::new(
+                            pos.0.x + bbox.x + HALF_BBOX_WIDTH,
+                            pos.0.y + bbox.y + bbox.h - HALF_BBOX_WIDTH,
+                        ),
+                        Point2::new(
+                            pos.0.x + bbox.x + bbox.w - HALF_BBOX_WIDTH,
+                            pos.0.y + bbox.y + bbox.h - HALF_BBOX_WIDTH,
+                        ),
+                    ],
+                    BBOX_WIDTH,
+                    Color::from_rgb(255, 0, 0),
+                )?;
+                mb.line(
+                    &[
+                        Point2::new(
+                            pos.0.x + bbox.x + HALF_BBOX_WIDTH,
+                            pos.0.y + bbox.y + HALF_BBOX_WIDTH,
+                        ),
+                        Point2::new(
+                            pos.0.x + bbox.x + HALF_BBOX_WIDTH,
+                            pos.0.y + bbox.y + bbox.h - HALF_BBOX_WIDTH,
+                        ),
+                    ],
+                    BBOX_WIDTH,
+                    Color::from_rgb(255, 0, 0),
+                )?;
+                mb.line(
+                    &[
+                        Point2::new(
+                            pos.0.x + bbox.x + bbox.w - HALF_BBOX_WIDTH,
+                            pos.0.y + bbox.y + HALF_BBOX_WIDTH,
+                        ),
+                        Point2::new(
+                            pos.0.x + bbox.x + bbox.w - HALF_BBOX_WIDTH,
+                            pos.0.y + bbox.y + bbox.h - HALF_BBOX_WIDTH,
+                        ),
+                    ],
+                    BBOX_WIDTH,
+                    Color::from_rgb(255, 0, 0),
+                )?;
+            }
+
+            // bounds without pos
+            for (_id, BoundingBox(bbox)) in
+                &mut self.world.query::<Without<Position, &BoundingBox>>()
+            {
+                const BBOX_WIDTH: f32 = 2.0;
+                const HALF_BBOX_WIDTH: f32 = BBOX_WIDTH / 2.0;
+                mb.line(
+                    &[
+                        Point2::new(bbox.x + HALF_BBOX_WIDTH, bbox.y + HALF_BBOX_WIDTH),
+                        Point2::new(bbox.x + bbox.w - HALF_BBOX_WIDTH, bbox.y + HALF_BBOX_WIDTH),
+                    ],
+                    BBOX_WIDTH,
+                    Color::from_rgb(255, 0, 0),
+                )?;
+                mb.line(
+                    &[
+                        Point2::new(bbox.x + HALF_BBOX_WIDTH, bbox.y + bbox.h - HALF_BBOX_WIDTH),
+                        Point2::new(
+                            bbox.x + bbox.w - HALF_BBOX_WIDTH,
+                            bbox.y + bbox.h - HALF_BBOX_WIDTH,
+                        ),
+                    ],
+                    BBOX_WIDTH,
+                    Color::from_rgb(255, 0, 0),
+                )?;
+                mb.line(
+                    &[
+                        Point2::new(bbox.x + HALF_BBOX_WIDTH, bbox.y + HALF_BBOX_WIDTH),
+                        Point2::new(bbox.x + HALF_BBOX_WIDTH, bbox.y + bbox.h - HALF_BBOX_WIDTH),
+                    ],
+                    BBOX_WIDTH,
+                    Color::from_rgb(255, 0, 0),
+                )?;
+                mb.line(
+                    &[
+                        Point2::new(bbox.x + bbox.w - HALF_BBOX_WIDTH, bbox.y + HALF_BBOX_WIDTH),
+                        Point2::new(
+                            bbox.x + bbox.w - HALF_BBOX_WIDTH,
+                            bbox.y + bbox.h - HALF_BBOX_WIDTH,
+                        ),
+                    ],
+                    BBOX_WIDTH,
+                    Color::from_rgb(255, 0, 0),
+                )?;
+            }
+
+            let mesh = mb.build(ctx)?;
+            graphics::draw(
+                ctx,
+                &mesh,
+                (relative_point(self.camera.center, Point2::new(0.0, 0.0)),),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use ggez::graphics;
+use ggez::graphics::Text;
+
+use crate::common::*;
+use crate::scenes::*;
+use crate::stats::format_ticks;
+
+impl GameState {
+    pub fn draw_results(&mut self, ctx: &mut Context) -> GameResult<()> {
+        draw_overlay(ctx)?;
+
+        let mut lines = self.stats.summary();
+        lines.push(format!(
+            "Total: {}",
+            format_ticks(self.speedrun.elapsed(self.tick))
+        ));
+        lines.push(String::new());
+        lines.push("Press Enter to continue".to_string());
+
+        graphics::draw(
+            ctx,
+            &Text::new(
+                graphics::TextFragment::new("Results")
+                    .color(graphics::WHITE)
+                    .scale(graphics::Scale::uniform(60.0)),
+            ),
+            (Point2::new(MIDDLE_X - 200.0, 120.0),),
+        )?;
+
+        for (index, line) in lines.into_iter().enumerate() {
+            graphics::draw(
+                ctx,
+                &Text::new(
+                    graphics::TextFragment::new(line)
+                        .color(graphics::WHITE)
+                        .scale(graphics::Scale::uniform(30.0)),
+                ),
+                (Point2::new(MIDDLE_X - 200.0, 220.0 + index as f32 * 40.0),),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn menu_results(&mut self, input: MenuInput) {
+        if input == MenuInput::Select || input == MenuInput::Back {
+            self.scenes.pop();
+        }
+    }
+}
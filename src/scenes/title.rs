@@ -0,0 +1,79 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ggez::event::quit;
+use ggez::graphics;
+
+use crate::common::*;
+use crate::scenes::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TitleItem {
+    Continue,
+    Start,
+    LevelSelect,
+    RandomLevel,
+    Options,
+    Quit,
+}
+
+impl TitleItem {
+    fn label(self) -> &'static str {
+        match self {
+            TitleItem::Continue => "Continue",
+            TitleItem::Start => "Start",
+            TitleItem::LevelSelect => "Level Select",
+            TitleItem::RandomLevel => "Random Level",
+            TitleItem::Options => "Options",
+            TitleItem::Quit => "Quit",
+        }
+    }
+}
+
+impl GameState {
+    fn title_items(&self) -> Vec<TitleItem> {
+        let mut items = vec![];
+        if self.save.checkpoint.is_some() {
+            items.push(TitleItem::Continue);
+        }
+        items.extend_from_slice(&[
+            TitleItem::Start,
+            TitleItem::LevelSelect,
+            TitleItem::RandomLevel,
+            TitleItem::Options,
+            TitleItem::Quit,
+        ]);
+        items
+    }
+
+    pub fn draw_title(&mut self, ctx: &mut Context, selected: usize) -> GameResult<()> {
+        let items = self
+            .title_items()
+            .into_iter()
+            .map(|item| item.label().to_string())
+            .collect::<Vec<_>>();
+        draw_menu(ctx, "Platformer", &items, selected, graphics::BLACK)
+    }
+
+    pub fn menu_title(&mut self, ctx: &mut Context, selected: usize, input: MenuInput) {
+        let items = self.title_items();
+        match input {
+            MenuInput::Select => match items.get(selected) {
+                Some(TitleItem::Continue) => self.continue_game(),
+                Some(TitleItem::Start) => self.play_level("start".to_string()),
+                Some(TitleItem::LevelSelect) => {
+                    self.scenes.push(Scene::LevelSelect { selected: 0 })
+                }
+                Some(TitleItem::RandomLevel) => {
+                    let seed = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |time| time.as_millis() as u64);
+                    self.play_generated_level(seed)
+                }
+                Some(TitleItem::Options) => self.scenes.push(Scene::Options { selected: 0 }),
+                Some(TitleItem::Quit) | None => quit(ctx),
+            },
+            MenuInput::Back => quit(ctx),
+            _ => self.select(navigate(selected, items.len(), &input)),
+        }
+    }
+}
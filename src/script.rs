@@ -0,0 +1,292 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use ggez::graphics;
+use ggez::graphics::Rect;
+use rhai::{Dynamic, Engine, Module, RegisterFn, Scope, AST};
+
+use crate::common::*;
+use crate::components::*;
+use crate::events::GameEvent;
+use crate::level::{default_message_ticks, Layer};
+
+/// Changes requested by a script, applied to the world once the callback returns.
+#[derive(Debug)]
+pub enum ScriptCommand {
+    SpawnPlatform {
+        name: String,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    Despawn(String),
+    MovePlatform {
+        name: String,
+        x: f32,
+        y: f32,
+    },
+    SetFlag(String),
+    ClearFlag(String),
+    ShowText(String),
+    ChangeLevel(String),
+}
+
+pub struct LevelScript {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    /// Names of the functions the script defines, other callbacks are skipped.
+    callbacks: HashSet<String>,
+    commands: Rc<RefCell<Vec<ScriptCommand>>>,
+    flags: Rc<RefCell<HashSet<String>>>,
+}
+
+impl LevelScript {
+    pub fn compile(path: &str) -> Option<AST> {
+        let source = match std::fs::read_to_string(format!("resources/{}", path)) {
+            Ok(source) => source,
+            Err(e) => {
+                println!(
+                    "failed to read script ({}) with the following error: {}",
+                    path, e
+                );
+                return None;
+            }
+        };
+
+        match Engine::new().compile(&source) {
+            Ok(ast) => Some(ast),
+            Err(e) => {
+                println!(
+                    "failed to compile script ({}) with the following error: {}",
+                    path, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Runs the top level statements of a compiled script in a fresh scope.
+    pub fn new(path: &str, ast: AST) -> Option<Self> {
+        let commands = Rc::new(RefCell::new(vec![]));
+        let flags = Rc::new(RefCell::new(HashSet::new()));
+        let mut engine = Engine::new();
+
+        let c = commands.clone();
+        engine.register_fn(
+            "spawn_platform",
+            move |name: &str, x: f64, y: f64, width: f64, height: f64| {
+                c.borrow_mut().push(ScriptCommand::SpawnPlatform {
+                    name: name.to_string(),
+                    x: x as f32,
+                    y: y as f32,
+                    width: width as f32,
+                    height: height as f32,
+                })
+            },
+        );
+        let c = commands.clone();
+        engine.register_fn("despawn", move |name: &str| {
+            c.borrow_mut()
+                .push(ScriptCommand::Despawn(name.to_string()))
+        });
+        let c = commands.clone();
+        engine.register_fn("move_platform", move |name: &str, x: f64, y: f64| {
+            c.borrow_mut().push(ScriptCommand::MovePlatform {
+                name: name.to_string(),
+                x: x as f32,
+                y: y as f32,
+            })
+        });
+        let (c, f) = (commands.clone(), flags.clone());
+        engine.register_fn("set_flag", move |flag: &str| {
+            f.borrow_mut().insert(flag.to_string());
+            c.borrow_mut()
+                .push(ScriptCommand::SetFlag(flag.to_string()))
+        });
+        let (c, f) = (commands.clone(), flags.clone());
+        engine.register_fn("clear_flag", move |flag: &str| {
+            f.borrow_mut().remove(flag);
+            c.borrow_mut()
+                .push(ScriptCommand::ClearFlag(flag.to_string()))
+        });
+        let f = flags.clone();
+        engine.register_fn("has_flag", move |flag: &str| f.borrow().contains(flag));
+        let c = commands.clone();
+        engine.register_fn("show_text", move |text: &str| {
+            c.borrow_mut()
+                .push(ScriptCommand::ShowText(text.to_string()))
+        });
+        let c = commands.clone();
+        engine.register_fn("change_level", move |level: &str| {
+            c.borrow_mut()
+                .push(ScriptCommand::ChangeLevel(level.to_string()))
+        });
+
+        let mut scope = Scope::new();
+        if let Err(e) = engine.consume_ast_with_scope(&mut scope, &ast) {
+            println!(
+                "failed to run script ({}) with the following error: {}",
+                path, e
+            );
+            return None;
+        }
+
+        let callbacks = AsRef::<Module>::as_ref(&ast)
+            .iter_script_fn()
+            .map(|function| function.name.clone())
+            .collect();
+
+        Some(LevelScript {
+            engine,
+            ast,
+            scope,
+            callbacks,
+            commands,
+            flags,
+        })
+    }
+
+    /// Calls a callback if the script defines it, and returns the commands it issued.
+    pub fn call(
+        &mut self,
+        flags: &HashSet<String>,
+        callback: &str,
+        mut args: Vec<Dynamic>,
+    ) -> Vec<ScriptCommand> {
+        if !self.callbacks.contains(callback) {
+            return vec![];
+        }
+        *self.flags.borrow_mut() = flags.clone();
+
+        if let Err(e) =
+            self.engine
+                .call_fn_dynamic(&mut self.scope, &self.ast, callback, None, &mut args)
+        {
+            println!("script callback {} failed: {}", callback, e);
+        }
+
+        std::mem::take(&mut *self.commands.borrow_mut())
+    }
+}
+
+impl GameState {
+    /// Scripts are compiled once per path and rerun from the start whenever their level is entered.
+    pub fn load_script(&mut self) {
+        let path = match self.levels[&self.current_level].script.clone() {
+            Some(path) => path,
+            None => {
+                self.script = None;
+                return;
+            }
+        };
+
+        let ast = match self.script_asts.get(&path) {
+            Some(ast) => Some(ast.clone()),
+            None => LevelScript::compile(&path).inspect(|ast| {
+                self.script_asts.insert(path.clone(), ast.clone());
+            }),
+        };
+        self.script = ast.and_then(|ast| LevelScript::new(&path, ast));
+    }
+
+    pub fn call_script(&mut self, callback: &str, args: Vec<Dynamic>) -> GameResult<()> {
+        let commands = match &mut self.script {
+            Some(script) => script.call(&self.flags, callback, args),
+            None => return Ok(()),
+        };
+
+        for command in commands {
+            self.run_script_command(command)?;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn update_script(&mut self) -> GameResult<()> {
+        self.call_script("on_tick", vec![])
+    }
+
+    pub fn handle_script_event(&mut self, event: &GameEvent) -> GameResult<()> {
+        match event {
+            GameEvent::TriggerEntered { trigger, entity }
+                if self.world.get::<Player>(*entity).is_ok() =>
+            {
+                let name = match self.world.get::<Name>(*trigger) {
+                    Ok(name) => name.0.clone(),
+                    Err(_) => return Ok(()),
+                };
+                self.call_script("on_trigger", vec![Dynamic::from(name)])
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn named(&self, name: &str) -> Vec<Entity> {
+        self.world
+            .query::<&Name>()
+            .iter()
+            .filter(|(_, n)| n.0 == name)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Script coordinates are in grid units with y pointing up, just like the level files.
+    fn run_script_command(&mut self, command: ScriptCommand) -> GameResult<()> {
+        let size = self.config.player.size;
+        let height = self.levels[&self.current_level].size.height;
+
+        match command {
+            ScriptCommand::SpawnPlatform {
+                name,
+                x,
+                y,
+                width,
+                height: h,
+            } => {
+                let width = width * size + 1.0;
+                let h = h * size + 1.0;
+                let (x, y) = (x * size, height - y * size);
+                self.world.spawn((
+                    Shape {
+                        rect: Rect::new(0.0, 0.0, width, h),
+                        color: graphics::BLACK,
+                    },
+                    ZOrder(20),
+                    BoundingBox(Rect::new(x, y, width, h)),
+                    Collider::new(&[Layer::Solid], &[]),
+                    Name(name),
+                    LevelId(DefaultAtom::from(self.current_level.clone())),
+                ));
+            }
+            ScriptCommand::Despawn(name) => {
+                for id in self.named(&name) {
+                    let _ = self.world.despawn(id);
+                }
+            }
+            ScriptCommand::MovePlatform { name, x, y } => {
+                for id in self.named(&name) {
+                    if let Ok(mut bbox) = self.world.get_mut::<BoundingBox>(id) {
+                        bbox.0.x = x * size;
+                        bbox.0.y = height - y * size;
+                    }
+                }
+            }
+            ScriptCommand::SetFlag(flag) => {
+                self.flags.insert(flag);
+            }
+            ScriptCommand::ClearFlag(flag) => {
+                self.flags.remove(&flag);
+            }
+            ScriptCommand::ShowText(text) => {
+                self.message = Some((text, self.tick + default_message_ticks()))
+            }
+            ScriptCommand::ChangeLevel(level) => {
+                self.events.publish(GameEvent::LevelChangeRequested(level))
+            }
+        }
+        Ok(())
+    }
+}
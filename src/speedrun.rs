@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use ggez::graphics;
+use ggez::graphics::{Color, Text};
+
+use crate::common::*;
+use crate::stats::format_ticks;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Split {
+    pub level: String,
+    /// time since the start of the run when the level was left
+    pub ticks: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PersonalBest {
+    #[serde(default)]
+    pub best_segments: BTreeMap<String, usize>,
+    #[serde(rename = "split")]
+    #[serde(default)]
+    pub splits: Vec<Split>,
+}
+
+impl PersonalBest {
+    pub fn total(&self) -> Option<usize> {
+        self.splits.last().map(|split| split.ticks)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SplitComparison {
+    Gold,
+    Ahead,
+    Behind,
+    Unknown,
+}
+
+#[derive(Debug, Default)]
+pub struct Speedrun {
+    /// Where the personal best is written, nothing is written without one.
+    pub path: Option<PathBuf>,
+    pub started: Option<usize>,
+    pub finished: Option<usize>,
+    pub splits: Vec<Split>,
+    pub personal_best: PersonalBest,
+    /// snapshot of the personal best at the start of the run
+    pub comparison: PersonalBest,
+}
+
+impl Speedrun {
+    pub fn load(path: PathBuf) -> Self {
+        let personal_best = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| match toml::from_str::<PersonalBest>(&data) {
+                Ok(pb) => Some(pb),
+                Err(e) => {
+                    println!(
+                        "failed to parse personal best file ({:?}) with the following error: {}",
+                        path, e
+                    );
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Speedrun {
+            path: Some(path),
+            started: None,
+            finished: None,
+            splits: vec![],
+            comparison: personal_best.clone(),
+            personal_best,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.started = None;
+        self.finished = None;
+        self.splits.clear();
+    }
+
+    pub fn elapsed(&self, tick: usize) -> usize {
+        match (self.started, self.finished) {
+            (_, Some(finished)) => finished,
+            (Some(started), None) => tick - started,
+            (None, None) => 0,
+        }
+    }
+
+    /// Takes a split when the player moves from one level to another.
+    ///
+    /// The run starts when leaving `start`, finishes when reaching `end` and resets when
+    /// returning to `start`.
+    pub fn change_level(&mut self, from: &str, to: &str, tick: usize) {
+        if from == to {
+            return;
+        }
+
+        if to == "start" {
+            self.reset();
+            return;
+        }
+
+        if from == "start" {
+            self.reset();
+            self.started = Some(tick);
+            self.comparison = self.personal_best.clone();
+            return;
+        }
+
+        if self.finished.is_some() {
+            return;
+        }
+
+        if let Some(started) = self.started {
+            self.splits.push(Split {
+                level: from.to_string(),
+                ticks: tick - started,
+            });
+
+            if to == "end" {
+                self.finished = Some(tick - started);
+                self.finish();
+            }
+        }
+    }
+
+    fn segment(&self, index: usize) -> usize {
+        let previous = if index == 0 {
+            0
+        } else {
+            self.splits[index - 1].ticks
+        };
+        self.splits[index].ticks - previous
+    }
+
+    pub fn compare(&self, index: usize) -> SplitComparison {
+        let split = &self.splits[index];
+        let segment = self.segment(index);
+
+        if self
+            .comparison
+            .best_segments
+            .get(&split.level)
+            .is_some_and(|best| segment < *best)
+        {
+            return SplitComparison::Gold;
+        }
+
+        match self.comparison.splits.get(index) {
+            Some(pb) if pb.level == split.level => {
+                if split.ticks <= pb.ticks {
+                    SplitComparison::Ahead
+                } else {
+                    SplitComparison::Behind
+                }
+            }
+            _ => SplitComparison::Unknown,
+        }
+    }
+
+    fn finish(&mut self) {
+        for index in 0..self.splits.len() {
+            let segment = self.segment(index);
+            let best = self
+                .personal_best
+                .best_segments
+                .entry(self.splits[index].level.clone())
+                .or_insert(segment);
+            if segment < *best {
+                *best = segment;
+            }
+        }
+
+        let is_personal_best = match (self.finished, self.personal_best.total()) {
+            (Some(total), Some(pb)) => total < pb,
+            (Some(_), None) => true,
+            _ => false,
+        };
+        if is_personal_best {
+            self.personal_best.splits = self.splits.clone();
+        }
+
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        match toml::to_string(&self.personal_best) {
+            Ok(data) => {
+                if let Err(e) = std::fs::write(path, data) {
+                    println!(
+                        "failed to write personal best file ({:?}) with the following error: {}",
+                        path, e
+                    );
+                }
+            }
+            Err(e) => println!("failed to serialize personal best: {}", e),
+        }
+    }
+}
+
+impl GameState {
+    pub fn draw_speedrun(&mut self, ctx: &mut Context) -> GameResult<()> {
+        const X: f32 = WIDTH - 260.0;
+
+        graphics::draw(
+            ctx,
+            &Text::new(
+                graphics::TextFragment::new(format_ticks(self.speedrun.elapsed(self.tick)))
+                    .color(Color::from_rgb(0, 0, 0))
+                    .scale(graphics::Scale::uniform(30.0)),
+            ),
+            (Point2::new(X, 2.0),),
+        )?;
+
+        for (index, split) in self.speedrun.splits.iter().enumerate() {
+            let color = match self.speedrun.compare(index) {
+                SplitComparison::Gold => Color::from_rgb(218, 165, 32),
+                SplitComparison::Ahead => Color::from_rgb(0, 160, 0),
+                SplitComparison::Behind => Color::from_rgb(200, 0, 0),
+                SplitComparison::Unknown => Color::from_rgb(0, 0, 0),
+            };
+
+            graphics::draw(
+                ctx,
+                &Text::new(
+                    graphics::TextFragment::new(format!(
+                        "{:<10} {}",
+                        split.level,
+                        format_ticks(split.ticks)
+                    ))
+                    .color(color)
+                    .scale(graphics::Scale::uniform(20.0)),
+                ),
+                (Point2::new(X, 36.0 + index as f32 * 22.0),),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::common::*;
+use crate::events::GameEvent;
+
+#[derive(Default, Debug)]
+pub struct LevelStats {
+    pub collected: HashSet<usize>,
+    pub total: usize,
+    pub deaths: u32,
+    pub ticks: usize,
+}
+
+#[derive(Default, Debug)]
+pub struct RunStats {
+    pub score: u32,
+    pub levels: BTreeMap<String, LevelStats>,
+    pub level_started: usize,
+    pub last_death: Option<usize>,
+}
+
+impl RunStats {
+    pub fn enter_level(&mut self, level: &str, total: usize, tick: usize) {
+        self.levels.entry(level.to_string()).or_default().total = total;
+        self.level_started = tick;
+    }
+
+    pub fn leave_level(&mut self, level: &str, tick: usize) {
+        self.levels.entry(level.to_string()).or_default().ticks += tick - self.level_started;
+        self.level_started = tick;
+    }
+
+    /// Dying of several causes in the same tick counts once.
+    pub fn record_death(&mut self, level: &str, tick: usize) {
+        if self.last_death != Some(tick) {
+            self.last_death = Some(tick);
+            self.levels.entry(level.to_string()).or_default().deaths += 1;
+        }
+    }
+
+    pub fn record_pickup(&mut self, level: &str, index: usize, value: u32) {
+        if self
+            .levels
+            .entry(level.to_string())
+            .or_default()
+            .collected
+            .insert(index)
+        {
+            self.score += value;
+        }
+    }
+
+    pub fn collected_count(&self) -> usize {
+        self.levels
+            .values()
+            .map(|stats| stats.collected.len())
+            .sum()
+    }
+
+    pub fn is_collected(&self, level: &str, index: usize) -> bool {
+        self.levels
+            .get(level)
+            .is_some_and(|stats| stats.collected.contains(&index))
+    }
+
+    /// One line per level, skipping the `start` hub and the `end` screen.
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = vec![format!("Score: {}", self.score)];
+        for (name, stats) in self
+            .levels
+            .iter()
+            .filter(|(name, _)| *name != "start" && *name != "end")
+        {
+            lines.push(format!(
+                "{}: {}/{} items, {} deaths, {}",
+                name,
+                stats.collected.len(),
+                stats.total,
+                stats.deaths,
+                format_ticks(stats.ticks)
+            ));
+        }
+        lines
+    }
+}
+
+impl GameState {
+    pub fn handle_stats_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::CollectiblePicked {
+                level, index, kind, ..
+            } => self.stats.record_pickup(level, *index, kind.value()),
+            GameEvent::PlayerDied => self.stats.record_death(&self.current_level, self.tick),
+            _ => {}
+        }
+    }
+}
+
+pub fn format_ticks(ticks: usize) -> String {
+    let millis = ticks * 1000 / DESIRED_FPS as usize;
+    format!(
+        "{:02}:{:02}.{:03}",
+        millis / 60_000,
+        (millis / 1000) % 60,
+        millis % 1000
+    )
+}
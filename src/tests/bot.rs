@@ -0,0 +1,153 @@
+use super::*;
+
+const GAP: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 3
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 8
+height = 1
+
+[[platform]]
+x = 11
+y = 4
+width = 12
+height = 1
+
+[[teleporter]]
+x = 18
+y = 6
+width = 2
+height = 1
+target = "second"
+"#;
+
+const SECOND: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+"#;
+
+const LOCKED: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 8
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[collectible]]
+x = 6
+y = 3
+kind = "key"
+name = "gate"
+
+[[door]]
+x = 10
+y = 20
+width = 1
+height = 18
+key = "gate"
+
+[[teleporter]]
+x = 12
+y = 3
+width = 2
+height = 1
+target = "second"
+"#;
+
+/// A player that gets up to speed quickly and steers in the air, so routes are short enough to
+/// search in a debug build.
+fn nimble() -> Config {
+    let mut config = Config::default();
+    config.player.acceleration = 6.0;
+    config.player.allow_air_control = true;
+    config
+}
+
+#[test]
+fn bot_finds_a_route_that_replays() {
+    let route = Harness::with_config(nimble(), &[("start", GAP), ("second", SECOND)])
+        .state
+        .find_route("second")
+        .expect("no route found");
+    assert_eq!(route.level, "start");
+    assert_eq!(route.ticks, route.steps.iter().map(|s| s.ticks).sum());
+
+    let mut harness = Harness::with_config(nimble(), &[("start", GAP), ("second", SECOND)]);
+    for step in &route.steps {
+        harness.run(step.ticks, step.input);
+    }
+    harness.run(1, Input::NONE);
+
+    assert_eq!(harness.level(), "second");
+    assert_eq!(harness.deaths("start"), 0);
+}
+
+#[test]
+fn bot_leaves_the_state_at_the_start() {
+    let harness = Harness::with_config(nimble(), &[("start", GAP), ("second", SECOND)]);
+    let start = harness.position();
+    let tick = harness.state.tick;
+
+    assert!(harness.state.find_route("second").is_some());
+    assert_eq!(harness.state.tick, tick);
+    assert_eq!(harness.position(), start);
+    assert_eq!(harness.level(), "start");
+    assert_eq!(harness.deaths("start"), 0);
+}
+
+#[test]
+fn bot_needs_a_teleporter_to_the_target() {
+    let harness = Harness::new(&[("start", GAP), ("second", SECOND)]);
+
+    assert!(harness.state.find_route("end").is_none());
+}
+
+#[test]
+fn bot_picks_up_the_key_for_a_locked_door() {
+    // the door can't be jumped over, without jumps and with coarser states there are far fewer
+    // states to search
+    let mut config = nimble();
+    config.player.jump_acceleration = 0.0;
+    config.bot.position_step = 4.0;
+    config.bot.velocity_step = 1.0;
+
+    let route = Harness::with_config(config.clone(), &[("start", LOCKED), ("second", SECOND)])
+        .state
+        .find_route("second")
+        .expect("no route found");
+
+    let mut harness = Harness::with_config(config, &[("start", LOCKED), ("second", SECOND)]);
+    for step in &route.steps {
+        harness.run(step.ticks, step.input);
+    }
+
+    assert_eq!(harness.level(), "second");
+    assert!(harness.state.keys.contains("gate"));
+}
@@ -0,0 +1,47 @@
+use super::*;
+
+/// A config from before health, knockback and ladders were added to the player.
+const OLD_CONFIG: &str = r#"
+[player]
+mass = 10.0
+size = 32.0
+acceleration = 1.0
+jump_acceleration = 70.0
+float_modifier = 0.5
+allow_air_control = true
+
+[physics]
+max_horizontal_velocity = 10.0
+max_vertical_velocity = 10.0
+friction = 0.5
+normal_force = 1.0
+gravity = 0.2
+movement_deadzone = 0.0001
+
+[camera]
+deadzone = 16.0
+
+[debug]
+draw_grid = false
+draw_bounds = false
+"#;
+
+#[test]
+fn shipped_config_parses() {
+    toml::from_str::<Config>(include_str!("../../resources/config.toml")).unwrap();
+}
+
+#[test]
+fn missing_player_fields_have_defaults() {
+    let config = toml::from_str::<Config>(OLD_CONFIG).unwrap();
+    let defaults = Config::default();
+
+    assert!(config.player.allow_air_control);
+    assert_eq!(config.player.max_health, defaults.player.max_health);
+    assert_eq!(
+        config.player.invulnerability_ticks,
+        defaults.player.invulnerability_ticks
+    );
+    assert_eq!(config.player.knockback, defaults.player.knockback);
+    assert_eq!(config.player.climb_speed, defaults.player.climb_speed);
+}
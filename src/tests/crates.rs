@@ -0,0 +1,142 @@
+use super::*;
+
+const CRATE: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[platform]]
+x = 20
+y = 6
+width = 1
+height = 4
+
+[[crate]]
+x = 8
+y = 2
+width = 2
+height = 2
+"#;
+
+const SWITCH: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[switch]]
+x = 12
+y = 2
+flag = "gate"
+"#;
+
+fn crate_position(harness: &Harness) -> Point2 {
+    harness
+        .state
+        .world
+        .query::<(&Position, &Pushable)>()
+        .iter()
+        .map(|(_, (position, _))| position.0)
+        .next()
+        .expect("no crate in the world")
+}
+
+#[test]
+fn crate_falls_and_lands_on_the_floor() {
+    let level = CRATE.replace("y = 2\nwidth = 2", "y = 8\nwidth = 2");
+    let mut harness = Harness::new(&[("start", &level)]);
+
+    harness.run(120, Input::NONE);
+    assert_eq!(crate_position(&harness).y, harness.surface(2.0));
+}
+
+#[test]
+fn player_pushes_a_crate() {
+    let mut harness = Harness::new(&[("start", CRATE)]).settled();
+    let start = crate_position(&harness);
+    let size = harness.state.config.player.size;
+
+    for _ in 0..120 {
+        harness.tick(Input::RIGHT);
+        assert!(harness.position().x + size / 2.0 <= crate_position(&harness).x - size + 0.001);
+    }
+    assert!(crate_position(&harness).x > start.x);
+    assert!(harness.velocity().x > 0.0);
+    assert_eq!(crate_position(&harness).y, harness.surface(2.0));
+}
+
+#[test]
+fn crate_stops_at_a_wall_with_its_own_size() {
+    let mut harness = Harness::new(&[("start", CRATE)]);
+    let size = harness.state.config.player.size;
+    let wall = 20.0 * size;
+
+    harness.run(1000, Input::RIGHT);
+    assert!((crate_position(&harness).x + size - wall).abs() < 0.001);
+    assert!(harness.position().x < crate_position(&harness).x);
+}
+
+#[test]
+fn player_stands_on_a_crate() {
+    let level = CRATE.replace("x = 4\ny = 2", "x = 9\ny = 6");
+    let mut harness = Harness::new(&[("start", &level)]);
+
+    harness.run(120, Input::NONE);
+    assert!(harness.grounded());
+    assert_eq!(harness.position().y, harness.surface(4.0));
+}
+
+#[test]
+fn crate_holds_a_switch_down() {
+    let level = format!("{}\n[[crate]]\nx = 12\ny = 6\n", SWITCH);
+    let mut harness = Harness::new(&[("start", &level)]);
+
+    harness.run(120, Input::NONE);
+    assert!(harness.state.flags.contains("gate"));
+}
+
+#[test]
+fn switch_is_released_when_the_player_leaves() {
+    let mut harness = Harness::new(&[("start", SWITCH)]).settled();
+    assert!(!harness.state.flags.contains("gate"));
+
+    assert!(harness.run_until(300, Input::RIGHT, |h| h.state.flags.contains("gate")));
+    assert!(harness.run_until(300, Input::RIGHT, |h| !h.state.flags.contains("gate")));
+}
+
+#[test]
+fn slippery_crates_slide_further() {
+    let slide = |friction| {
+        let mut config = Config::default();
+        config.crates.friction = friction;
+        let mut harness = Harness::with_config(config, &[("start", CRATE)]).settled();
+        let start = crate_position(&harness);
+
+        for (_, (velocity, _)) in &mut harness.state.world.query::<(&mut Velocity, &Pushable)>() {
+            velocity.0.x = 2.0;
+        }
+        harness.run(120, Input::NONE);
+        crate_position(&harness).x - start.x
+    };
+
+    assert!(slide(0.3) > slide(1.0));
+}
@@ -0,0 +1,514 @@
+use super::*;
+use crate::level::default_message_ticks;
+use crate::save::Checkpoint;
+use crate::scenes::MenuInput;
+
+const FLOOR: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+"#;
+
+const WALL: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[platform]]
+x = 10
+y = 6
+width = 1
+height = 4
+"#;
+
+const PIT: &str = r#"
+[size]
+width = 30
+height = 10
+
+[start]
+x = 4
+y = 8
+
+[[platform]]
+x = 20
+y = 2
+width = 5
+height = 1
+"#;
+
+const TELEPORTER: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[teleporter]]
+x = 10
+y = 3
+width = 2
+height = 1
+target = "second"
+"#;
+
+const HAZARDS: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[trap]]
+x = 8
+y = 3
+width = 1
+height = 1
+
+[[teleporter]]
+x = 14
+y = 3
+width = 2
+height = 1
+target = "second"
+"#;
+
+const LOCKED: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[collectible]]
+x = 8
+y = 3
+kind = "key"
+name = "gate"
+
+[[door]]
+x = 14
+y = 4
+width = 1
+height = 2
+key = "gate"
+"#;
+
+const RAMP: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[slope]]
+x = 8
+width = 6
+left = 2
+right = 5
+
+[[platform]]
+x = 14
+y = 5
+width = 16
+height = 3
+"#;
+
+#[test]
+fn player_lands_on_the_floor() {
+    let harness = Harness::new(&[("start", FLOOR)]).settled();
+
+    assert!(harness.grounded());
+    assert_eq!(harness.position().y, harness.surface(2.0));
+    assert_eq!(harness.velocity(), Vector2::new(0.0, 0.0));
+}
+
+#[test]
+fn running_moves_the_player_right_and_friction_stops_it() {
+    let mut harness = Harness::new(&[("start", FLOOR)]).settled();
+    let start = harness.position();
+
+    harness.run(30, Input::RIGHT);
+    assert!(harness.position().x > start.x);
+    assert!(harness.velocity().x > 0.0);
+    assert!(harness.grounded());
+
+    harness.run(200, Input::NONE);
+    assert_eq!(harness.velocity().x, 0.0);
+    assert_eq!(harness.position().y, harness.surface(2.0));
+}
+
+#[test]
+fn jump_rises_and_lands_again() {
+    let mut harness = Harness::new(&[("start", FLOOR)]).settled();
+    let floor = harness.surface(2.0);
+
+    harness.run(1, Input::JUMP);
+    assert!(!harness.grounded());
+
+    harness.run(10, Input::NONE);
+    assert!(harness.position().y < floor - harness.state.config.player.size);
+
+    assert!(harness.run_until(200, Input::NONE, Harness::grounded));
+    assert_eq!(harness.position().y, floor);
+}
+
+#[test]
+fn holding_jump_floats_higher() {
+    fn apex(held: Input) -> f32 {
+        let mut harness = Harness::new(&[("start", FLOOR)]).settled();
+        let mut apex = harness.position().y;
+        harness.tick(Input::JUMP);
+        while !harness.grounded() {
+            harness.tick(held);
+            apex = apex.min(harness.position().y);
+        }
+        apex
+    }
+
+    assert!(apex(Input::JUMP) < apex(Input::NONE));
+}
+
+#[test]
+fn scripted_run_back_and_forth() {
+    let mut harness = Harness::new(&[("start", FLOOR)]).settled();
+    let start = harness.position();
+
+    harness.run_script(&[(40, Input::RIGHT), (100, Input::NONE)]);
+    let right = harness.position();
+    assert!(right.x > start.x);
+
+    harness.run_script(&[(40, Input::LEFT), (100, Input::NONE)]);
+    assert!(harness.position().x < right.x);
+    assert!(harness.grounded());
+}
+
+#[test]
+fn wall_blocks_the_player() {
+    let mut harness = Harness::new(&[("start", WALL)]).settled();
+    let wall = 10.0 * harness.state.config.player.size;
+
+    harness.run(200, Input::RIGHT);
+    assert!(harness.position().x + harness.state.config.player.size / 2.0 <= wall);
+    assert!(harness.grounded());
+}
+
+#[test]
+fn falling_out_of_the_level_counts_a_death_and_restarts() {
+    let mut harness = Harness::new(&[("start", PIT)]);
+    let start = harness.position();
+
+    assert!(harness.run_until(200, Input::NONE, |h| h.deaths("start") == 1));
+
+    assert_eq!(harness.level(), "start");
+    assert_eq!(harness.position(), start);
+}
+
+#[test]
+fn a_death_is_counted_once_and_a_reset_not_at_all() {
+    let mut harness = Harness::new(&[("start", FLOOR)]).settled();
+
+    harness.state.events.publish(GameEvent::PlayerDied);
+    harness.state.events.publish(GameEvent::PlayerDied);
+    harness.state.events.publish(GameEvent::RestartRequested);
+    harness.run(2, Input::NONE);
+    assert_eq!(harness.deaths("start"), 1);
+
+    harness.state.events.publish(GameEvent::RestartRequested);
+    harness.run(2, Input::NONE);
+    assert_eq!(harness.deaths("start"), 1);
+}
+
+#[test]
+fn continuing_keeps_the_saved_progress_on_the_start_level() {
+    let level = format!(
+        "{}\n[[collectible]]\nx = 20\ny = 4\nkind = \"key\"\nname = \"gate\"\n",
+        FLOOR
+    );
+    let mut harness = Harness::new(&[("start", &level), ("second", FLOOR)]).settled();
+    harness
+        .state
+        .events
+        .publish(GameEvent::LevelChangeRequested("second".to_string()));
+    harness.run(1, Input::NONE);
+    assert_eq!(harness.level(), "second");
+
+    let size = harness.state.config.player.size;
+    harness
+        .state
+        .save
+        .collectibles
+        .insert("start".to_string(), vec![0].into_iter().collect());
+    harness.state.save.checkpoint = Some(Checkpoint {
+        level: "start".to_string(),
+        x: 4.0 * size,
+        y: harness.surface(2.0) - 1.0,
+    });
+    harness.state.continue_game();
+    harness.run(1, Input::NONE);
+
+    assert_eq!(harness.level(), "start");
+    assert!(harness.state.keys.contains("gate"));
+    assert_eq!(harness.state.stats.collected_count(), 1);
+    assert_eq!(
+        harness.state.world.query::<&Collectible>().iter().count(),
+        0
+    );
+}
+
+#[test]
+fn scripts_spawn_and_despawn_platforms() {
+    const BRIDGE: &str = r#"
+        fn on_enter() {
+            spawn_platform("bridge", 10.0, 6.0, 3.0, 1.0);
+            show_text("A bridge appeared");
+        }
+
+        fn remove_bridge() {
+            despawn("bridge");
+        }
+    "#;
+    let level = format!("script = \"bridge.rhai\"\n{}", FLOOR);
+    let mut harness = Harness::new(&[("start", &level)]).settled();
+    let bridges = |harness: &Harness| {
+        harness
+            .state
+            .world
+            .query::<&Name>()
+            .iter()
+            .filter(|(_, name)| name.0 == "bridge")
+            .count()
+    };
+
+    // the script isn't a file, so it's compiled here instead of on the first load
+    let ast = rhai::Engine::new().compile(BRIDGE).unwrap();
+    harness
+        .state
+        .script_asts
+        .insert("bridge.rhai".to_string(), ast);
+    harness.state.change_level("start".to_string()).unwrap();
+    assert_eq!(bridges(&harness), 1);
+    let message = harness.state.message.clone().expect("no message shown");
+    assert_eq!(message.1, harness.state.tick + default_message_ticks());
+
+    harness.run(1, Input::NONE);
+    harness.state.call_script("remove_bridge", vec![]).unwrap();
+    assert_eq!(bridges(&harness), 0);
+}
+
+#[test]
+fn the_bridge_switch_of_level_2_spawns_the_bridge() {
+    let mut harness = Harness::from_dir("resources/levels", "level_2").settled();
+    // the enemy guarding the switch would knock the player back
+    let enemies = harness
+        .state
+        .world
+        .query::<&EnemyAi>()
+        .iter()
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    for id in enemies {
+        harness.state.world.despawn(id).unwrap();
+    }
+
+    assert!(harness.run_until(300, Input::RIGHT, |h| h
+        .state
+        .flags
+        .contains("level_2_bridge")));
+    assert!(harness
+        .state
+        .world
+        .query::<&Name>()
+        .iter()
+        .any(|(_, name)| name.0 == "bridge"));
+}
+
+#[test]
+fn locked_door_opens_with_its_key() {
+    let mut harness = Harness::new(&[("start", LOCKED)]).settled();
+    let size = harness.state.config.player.size;
+
+    assert!(harness.run_until(300, Input::RIGHT, |h| h.position().x > 16.0 * size));
+    assert!(harness.state.keys.contains("gate"));
+}
+
+#[test]
+fn locked_door_blocks_without_its_key() {
+    let level = LOCKED.replace("name = \"gate\"", "name = \"other\"");
+    let mut harness = Harness::new(&[("start", &level)]).settled();
+    let size = harness.state.config.player.size;
+
+    harness.run(300, Input::RIGHT);
+    assert!(harness.state.keys.contains("other"));
+    assert!(harness.position().x + size / 2.0 <= 14.0 * size);
+}
+
+#[test]
+fn keys_without_a_name_are_rejected() {
+    let level = toml::from_str::<Level>(&LOCKED.replace("name = \"gate\"\n", "")).unwrap();
+
+    assert!(level.validate().is_err());
+    assert!(toml::from_str::<Level>(LOCKED).unwrap().validate().is_ok());
+}
+
+#[test]
+fn quitting_to_the_title_resets_the_speedrun() {
+    let mut harness = Harness::new(&[("start", FLOOR), ("second", FLOOR)]).settled();
+    harness
+        .state
+        .events
+        .publish(GameEvent::LevelChangeRequested("second".to_string()));
+    harness.run(10, Input::NONE);
+    assert!(harness.state.speedrun.started.is_some());
+
+    harness.state.menu_paused(3, MenuInput::Select);
+    assert!(harness.state.speedrun.started.is_none());
+    assert!(harness.state.speedrun.splits.is_empty());
+}
+
+#[test]
+fn teleporter_changes_the_level() {
+    let mut harness = Harness::new(&[("start", TELEPORTER), ("second", FLOOR)]).settled();
+
+    assert!(harness.run_until(300, Input::RIGHT, |h| h.level() == "second"));
+    assert_eq!(harness.deaths("start"), 0);
+
+    harness.run(60, Input::NONE);
+    assert!(harness.grounded());
+    assert_eq!(harness.position().y, harness.surface(2.0));
+}
+
+#[test]
+fn traps_and_teleporters_dont_block_the_player() {
+    let mut harness = Harness::new(&[("start", HAZARDS), ("second", FLOOR)]).settled();
+    let player = harness.player();
+    // ignore the trap's damage and knockback, only its collision matters here
+    harness
+        .state
+        .world
+        .insert_one(player, Invulnerable(10_000))
+        .unwrap();
+    let floor = harness.surface(2.0);
+
+    let mut last_x = harness.position().x;
+    for _ in 0..300 {
+        harness.tick(Input::RIGHT);
+        if harness.level() != "start" {
+            break;
+        }
+        assert_eq!(harness.position().y, floor);
+        assert!(harness.position().x > last_x);
+        last_x = harness.position().x;
+    }
+
+    assert_eq!(harness.level(), "second");
+}
+
+#[test]
+fn default_start_level_is_playable() {
+    let mut harness = Harness::from_file("resources/levels/start.toml");
+    harness.run(60, Input::NONE);
+
+    assert!(harness.grounded());
+    assert_eq!(harness.position().y, harness.surface(12.0));
+}
+
+#[test]
+fn player_walks_up_and_down_a_slope_without_leaving_it() {
+    let mut harness = Harness::new(&[("start", RAMP)]).settled();
+    let size = harness.state.config.player.size;
+
+    for _ in 0..150 {
+        harness.tick(Input::RIGHT);
+        assert!(harness.grounded());
+        assert_eq!(harness.velocity().y, 0.0);
+    }
+    assert!(harness.position().x > 14.0 * size);
+    assert_eq!(harness.position().y, harness.surface(5.0));
+
+    for _ in 0..300 {
+        harness.tick(Input::LEFT);
+        assert!(harness.grounded());
+        assert_eq!(harness.velocity().y, 0.0);
+    }
+    assert!(harness.position().x < 8.0 * size);
+    assert_eq!(harness.position().y, harness.surface(2.0));
+}
+
+#[test]
+fn grounded_player_does_not_step_up_low_ledges() {
+    let level = format!(
+        "{}\n[[platform]]\nx = 10\ny = 2.25\nwidth = 5\nheight = 0.25\n",
+        FLOOR
+    );
+    let mut harness = Harness::new(&[("start", &level)]).settled();
+    let size = harness.state.config.player.size;
+
+    harness.run(200, Input::RIGHT);
+    assert!(harness.position().x + size / 2.0 <= 10.0 * size);
+    assert_eq!(harness.position().y, harness.surface(2.0));
+}
+
+#[test]
+fn player_lands_and_stands_still_on_a_slope() {
+    let level = RAMP.replace("x = 4\ny = 2", "x = 11\ny = 4");
+    let mut harness = Harness::new(&[("start", &level)]).settled();
+    let position = harness.position();
+
+    harness.run(60, Input::NONE);
+    assert!(harness.grounded());
+    assert_eq!(harness.position(), position);
+    assert!(position.y < harness.surface(2.0) && position.y > harness.surface(5.0));
+}
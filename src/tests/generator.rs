@@ -0,0 +1,34 @@
+use super::*;
+use crate::generator::{export_level, generate_level};
+use crate::reachability::{analyze, JumpModel};
+
+#[test]
+fn generated_levels_are_completable() {
+    let model = JumpModel::from_config(&Config::default()).unwrap();
+
+    for seed in 0..10 {
+        let mut level = generate_level(seed, "end", &model).expect("no level generated");
+        level.scale(model.size);
+        let report = analyze(&level, &model);
+
+        assert!(!report.start_falls, "seed {}", seed);
+        assert!(report.unreachable_teleporters.is_empty(), "seed {}", seed);
+    }
+}
+
+#[test]
+fn the_same_seed_generates_the_same_level() {
+    let model = JumpModel::from_config(&Config::default()).unwrap();
+    let level = |seed| export_level(&generate_level(seed, "end", &model).unwrap()).unwrap();
+
+    assert_eq!(level(7), level(7));
+    assert_ne!(level(7), level(8));
+}
+
+#[test]
+fn no_level_is_generated_without_a_way_to_the_teleporter() {
+    let mut model = JumpModel::from_config(&Config::default()).unwrap();
+    model.jump_impulse = 0.0;
+
+    assert!(generate_level(7, "end", &model).is_none());
+}
@@ -0,0 +1,223 @@
+//! Golden trajectories: the player's position and velocity per tick for canonical input scripts
+//! on every level in `resources/levels`.
+//!
+//! Missing or changed trajectories fail the test. To record new files or accept an intended change
+//! to the physics, rerun with `UPDATE_GOLDEN=1 cargo test` and commit the updated files.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use super::*;
+
+const LEVEL_DIR: &str = "resources/levels";
+const GOLDEN_DIR: &str = "tests/golden";
+const TOLERANCE: f32 = 0.001;
+const PLOT_WIDTH: usize = 60;
+const PLOT_HEIGHT: usize = 12;
+
+const SCRIPTS: &[(&str, &[(usize, Input)])] = &[
+    (
+        "jump_in_place",
+        &[(30, Input::NONE), (1, Input::JUMP), (119, Input::NONE)],
+    ),
+    (
+        "run_and_jump",
+        &[
+            (30, Input::NONE),
+            (40, Input::RIGHT),
+            (1, Input::RIGHT_JUMP),
+            (79, Input::RIGHT),
+        ],
+    ),
+    ("float_jump", &[(30, Input::NONE), (120, Input::JUMP)]),
+];
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    position: Point2,
+    velocity: Vector2,
+}
+
+impl Sample {
+    fn values(&self) -> [f32; 4] {
+        [
+            self.position.x,
+            self.position.y,
+            self.velocity.x,
+            self.velocity.y,
+        ]
+    }
+
+    fn deviation(&self, other: &Sample) -> f32 {
+        self.values()
+            .iter()
+            .zip(other.values().iter())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max)
+    }
+}
+
+fn record(level: &str, script: &[(usize, Input)]) -> Vec<Sample> {
+    let mut harness = Harness::from_dir(LEVEL_DIR, level);
+    let mut samples = vec![];
+    for &(ticks, input) in script {
+        for _ in 0..ticks {
+            harness.tick(input);
+            samples.push(Sample {
+                position: harness.position(),
+                velocity: harness.velocity(),
+            });
+        }
+    }
+    samples
+}
+
+fn serialize(samples: &[Sample]) -> String {
+    let mut data = String::from("# tick x y vx vy\n");
+    for (tick, sample) in samples.iter().enumerate() {
+        let [x, y, vx, vy] = sample.values();
+        writeln!(data, "{} {} {} {} {}", tick, x, y, vx, vy).unwrap();
+    }
+    data
+}
+
+fn deserialize(data: &str) -> Vec<Sample> {
+    data.lines()
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .map(|line| {
+            let values = line
+                .split_whitespace()
+                .skip(1)
+                .map(|value| value.parse::<f32>().expect("invalid golden file"))
+                .collect::<Vec<_>>();
+            Sample {
+                position: Point2::new(values[0], values[1]),
+                velocity: Vector2::new(values[2], values[3]),
+            }
+        })
+        .collect()
+}
+
+/// A summary of where the trajectories diverge, or `None` if they match.
+fn compare(expected: &[Sample], actual: &[Sample]) -> Option<String> {
+    let deviations = expected
+        .iter()
+        .zip(actual)
+        .map(|(e, a)| e.deviation(a))
+        .collect::<Vec<_>>();
+    let first = deviations.iter().position(|&d| d > TOLERANCE);
+
+    if first.is_none() && expected.len() == actual.len() {
+        return None;
+    }
+
+    let mut report = String::new();
+    if expected.len() != actual.len() {
+        writeln!(
+            report,
+            "  expected {} ticks, got {}",
+            expected.len(),
+            actual.len()
+        )
+        .unwrap();
+    }
+    if let Some(first) = first {
+        let (worst, max) =
+            deviations.iter().enumerate().fold(
+                (0, 0.0),
+                |(i, max), (j, &d)| if d > max { (j, d) } else { (i, max) },
+            );
+        writeln!(
+            report,
+            "  first diverges at tick {}: expected {:?}, got {:?}",
+            first,
+            expected[first].values(),
+            actual[first].values()
+        )
+        .unwrap();
+        writeln!(report, "  largest deviation {} at tick {}", max, worst).unwrap();
+    }
+
+    let xs = |samples: &[Sample]| samples.iter().map(|s| s.position.x).collect::<Vec<_>>();
+    let ys = |samples: &[Sample]| samples.iter().map(|s| -s.position.y).collect::<Vec<_>>();
+    report.push_str(&plot("x", &xs(expected), &xs(actual)));
+    report.push_str(&plot("height", &ys(expected), &ys(actual)));
+    Some(report)
+}
+
+/// Plots both series over time, `.` is expected, `o` is actual and `*` is both.
+fn plot(label: &str, expected: &[f32], actual: &[f32]) -> String {
+    let ticks = expected.len().max(actual.len());
+    let all = expected.iter().chain(actual).copied();
+    let min = all.clone().fold(f32::INFINITY, f32::min);
+    let max = all.fold(f32::NEG_INFINITY, f32::max);
+    let range = (max - min).max(f32::EPSILON);
+
+    let mut grid = vec![vec![' '; PLOT_WIDTH]; PLOT_HEIGHT];
+    let columns = (0..PLOT_WIDTH).map(|column| (column, column * ticks / PLOT_WIDTH));
+    for (column, tick) in columns {
+        for (series, mark) in [(expected, '.'), (actual, 'o')].iter() {
+            if let Some(value) = series.get(tick) {
+                let row = ((max - value) / range * (PLOT_HEIGHT - 1) as f32).round() as usize;
+                let cell = &mut grid[row][column];
+                *cell = if *cell == ' ' || *cell == *mark {
+                    *mark
+                } else {
+                    '*'
+                };
+            }
+        }
+    }
+
+    let mut plot = format!(
+        "  {} over {} ticks, from {} to {}\n",
+        label, ticks, min, max
+    );
+    for row in grid {
+        writeln!(plot, "  |{}|", row.into_iter().collect::<String>()).unwrap();
+    }
+    plot
+}
+
+#[test]
+fn trajectories_match_golden_files() {
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|value| value == "1");
+    let mut failures = vec![];
+
+    for (level, _) in level_files(LEVEL_DIR) {
+        for &(script, inputs) in SCRIPTS {
+            let path = PathBuf::from(GOLDEN_DIR).join(format!("{}_{}.txt", level, script));
+            let actual = record(&level, inputs);
+
+            match std::fs::read_to_string(&path) {
+                _ if update => {
+                    std::fs::create_dir_all(GOLDEN_DIR).unwrap();
+                    std::fs::write(&path, serialize(&actual)).unwrap();
+                    println!("recorded {}", path.display());
+                }
+                Ok(data) => {
+                    if let Some(report) = compare(&deserialize(&data), &actual) {
+                        failures.push(format!("{} on {}:\n{}", script, level, report));
+                    }
+                }
+                Err(e) => {
+                    failures.push(format!(
+                        "{} on {}:\n  can't read {}: {}\n",
+                        script,
+                        level,
+                        path.display(),
+                        e
+                    ));
+                }
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "{} trajectories changed or are missing, rerun with UPDATE_GOLDEN=1 if that's intended\n\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+    }
+}
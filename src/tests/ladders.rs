@@ -0,0 +1,94 @@
+use super::*;
+
+const LADDER: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 10.5
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[ladder]]
+x = 10
+y = 8
+height = 6
+"#;
+
+fn climbing(harness: &Harness) -> bool {
+    harness
+        .state
+        .world
+        .get::<Climbing>(harness.player())
+        .is_ok()
+}
+
+#[test]
+fn player_climbs_up_and_stands_on_top() {
+    let mut harness = Harness::new(&[("start", LADDER)]).settled();
+    let top = harness.surface(8.0);
+
+    assert!(harness.run_until(200, Input::UP, |h| h.position().y == top));
+    harness.run(60, Input::NONE);
+    assert!(!climbing(&harness));
+    assert!(harness.grounded());
+    assert_eq!(harness.position().y, top);
+}
+
+#[test]
+fn climbing_player_hangs_still() {
+    let mut harness = Harness::new(&[("start", LADDER)]).settled();
+
+    harness.run(20, Input::UP);
+    assert!(climbing(&harness));
+    assert_eq!(
+        harness.velocity().y,
+        -harness.state.config.player.climb_speed
+    );
+
+    let position = harness.position();
+    harness.run(30, Input::NONE);
+    assert!(climbing(&harness));
+    assert_eq!(harness.position(), position);
+}
+
+#[test]
+fn jump_lets_go_of_the_ladder() {
+    let mut harness = Harness::new(&[("start", LADDER)]).settled();
+    harness.run(20, Input::UP);
+
+    harness.run(1, Input::JUMP);
+    assert!(!climbing(&harness));
+    assert!(harness.velocity().y < 0.0);
+    assert!(harness.run_until(300, Input::NONE, Harness::grounded));
+}
+
+#[test]
+fn player_climbs_down_from_the_top() {
+    let level = LADDER.replace("y = 2\n\n[[platform]]", "y = 8\n\n[[platform]]");
+    let mut harness = Harness::new(&[("start", &level)]).settled();
+    assert!(harness.grounded());
+    assert_eq!(harness.position().y, harness.surface(8.0));
+
+    let floor = harness.surface(2.0);
+    assert!(harness.run_until(200, Input::DOWN, |h| h.position().y == floor));
+}
+
+#[test]
+fn player_walks_past_a_ladder() {
+    let level = LADDER.replace("x = 10.5", "x = 4");
+    let mut harness = Harness::new(&[("start", &level)]).settled();
+
+    for _ in 0..120 {
+        harness.tick(Input::RIGHT);
+        assert!(!climbing(&harness));
+        assert_eq!(harness.position().y, harness.surface(2.0));
+    }
+    assert!(harness.position().x > 11.0 * harness.state.config.player.size);
+}
@@ -0,0 +1,84 @@
+use super::*;
+
+fn floor(material: Option<&str>) -> String {
+    let level = format!(
+        r#"
+[size]
+width = 60
+height = 20
+
+[start]
+x = 4
+y = {}
+
+[[platform]]
+x = 0
+y = 2
+width = 60
+height = 1
+"#,
+        if material == Some("bouncy") { 8 } else { 2 }
+    );
+    match material {
+        Some(material) => format!("{}material = \"{}\"\n", level, material),
+        None => level,
+    }
+}
+
+/// The default config with the materials of the shipped one.
+fn harness(level: &str) -> Harness {
+    let config = Config {
+        materials: load_config().materials,
+        ..Config::default()
+    };
+    Harness::with_config(config, &[("start", level)])
+}
+
+/// How far the player slides after running right for a while and letting go.
+fn slide(material: Option<&str>) -> f32 {
+    let mut harness = harness(&floor(material)).settled();
+    harness.run(30, Input::RIGHT);
+    let released = harness.position().x;
+    assert!(harness.run_until(2000, Input::NONE, |h| h.velocity().x == 0.0));
+    harness.position().x - released
+}
+
+#[test]
+fn ice_slides_farther_than_plain_ground() {
+    assert!(slide(Some("ice")) > 2.0 * slide(None));
+}
+
+#[test]
+fn mud_limits_running_speed() {
+    let mut harness = harness(&floor(Some("mud"))).settled();
+    harness.run(300, Input::RIGHT);
+
+    let max = harness.state.config.physics.max_horizontal_velocity;
+    assert!(harness.velocity().x > 0.0);
+    assert!(harness.velocity().x <= max * harness.state.config.materials.0["mud"].speed);
+}
+
+#[test]
+fn bouncy_platform_bounces_the_player_and_comes_to_rest() {
+    let mut harness = harness(&floor(Some("bouncy")));
+
+    assert!(harness.run_until(200, Input::NONE, |h| h.velocity().y < 0.0));
+    assert!(!harness.grounded());
+
+    assert!(harness.run_until(2000, Input::NONE, Harness::grounded));
+    harness.run(10, Input::NONE);
+    assert!(harness.grounded());
+    assert_eq!(harness.position().y, harness.surface(2.0));
+}
+
+#[test]
+fn conveyor_carries_a_standing_player() {
+    let mut harness = harness(&floor(Some("conveyor_right"))).settled();
+    let start = harness.position();
+    harness.run(120, Input::NONE);
+
+    let conveyor = harness.state.config.materials.0["conveyor_right"].conveyor;
+    assert!(harness.grounded());
+    assert!(harness.position().x > start.x);
+    assert!((harness.velocity().x - conveyor).abs() < 0.001);
+}
@@ -0,0 +1,188 @@
+//! Gameplay tests that step the game without a window, driven by scripted inputs.
+
+use std::collections::HashMap;
+
+pub use crate::bot::Input;
+use crate::common::*;
+use crate::components::*;
+use crate::config::Config;
+use crate::events::GameEvent;
+use crate::game_state::load_config;
+use crate::level::Level;
+use crate::scenes::Scene;
+
+mod bot;
+mod config;
+mod crates;
+mod gameplay;
+mod generator;
+mod golden;
+mod ladders;
+mod materials;
+mod reachability;
+mod water;
+
+const SETTLE_TICKS: usize = 60;
+
+pub struct Harness {
+    pub state: GameState,
+    previous: Input,
+}
+
+impl Harness {
+    /// Loads levels from TOML strings, the game starts in the one called "start".
+    pub fn new(levels: &[(&str, &str)]) -> Self {
+        Harness::with_config(Config::default(), levels)
+    }
+
+    pub fn with_config(config: Config, levels: &[(&str, &str)]) -> Self {
+        let levels = levels
+            .iter()
+            .map(|(name, data)| {
+                let level = toml::from_str::<Level>(data)
+                    .unwrap_or_else(|e| panic!("failed to parse level {}: {}", name, e));
+                level
+                    .validate()
+                    .unwrap_or_else(|e| panic!("invalid level {}: {}", name, e));
+                (name.to_string(), level)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut state = GameState::from_levels(config, levels);
+        state.scenes = vec![Scene::Playing];
+        state.change_level("start".to_string()).unwrap();
+        state.process_events().unwrap();
+
+        Harness {
+            state,
+            previous: Input::NONE,
+        }
+    }
+
+    /// Loads a level file as the start level.
+    pub fn from_file(path: &str) -> Self {
+        let data = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("failed to read level {}: {}", path, e));
+        Harness::new(&[("start", &data)])
+    }
+
+    /// Loads all level files in `dir` with the shipped config and starts in `level`.
+    pub fn from_dir(dir: &str, level: &str) -> Self {
+        let files = level_files(dir);
+        let levels = files
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_str()))
+            .collect::<Vec<_>>();
+
+        let mut harness = Harness::with_config(load_config(), &levels);
+        if level != "start" {
+            harness.state.change_level(level.to_string()).unwrap();
+            harness.state.process_events().unwrap();
+        }
+        harness
+    }
+
+    /// Lets the player fall onto the ground and come to rest before a test starts.
+    pub fn settled(mut self) -> Self {
+        self.run(SETTLE_TICKS, Input::NONE);
+        self
+    }
+
+    /// Holds `input` for the given number of ticks.
+    pub fn run(&mut self, ticks: usize, input: Input) -> &mut Self {
+        for _ in 0..ticks {
+            self.tick(input);
+        }
+        self
+    }
+
+    /// Holds `input` until `condition` holds, for at most `max_ticks`. Returns whether it held.
+    pub fn run_until(
+        &mut self,
+        max_ticks: usize,
+        input: Input,
+        condition: impl Fn(&Harness) -> bool,
+    ) -> bool {
+        for _ in 0..max_ticks {
+            self.tick(input);
+            if condition(self) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Runs a sequence of inputs, each held for the given number of ticks.
+    pub fn run_script(&mut self, script: &[(usize, Input)]) -> &mut Self {
+        for &(ticks, input) in script {
+            self.run(ticks, input);
+        }
+        self
+    }
+
+    pub fn tick(&mut self, input: Input) {
+        input.apply(&mut self.state.controls, self.previous);
+        self.previous = input;
+
+        self.state.step().unwrap();
+    }
+
+    fn player(&self) -> Entity {
+        self.state
+            .world
+            .query::<&Player>()
+            .iter()
+            .map(|(id, _)| id)
+            .next()
+            .expect("no player in the world")
+    }
+
+    /// Position of the bottom center of the player in pixels.
+    pub fn position(&self) -> Point2 {
+        self.state.world.get::<Position>(self.player()).unwrap().0
+    }
+
+    pub fn velocity(&self) -> Vector2 {
+        self.state.world.get::<Velocity>(self.player()).unwrap().0
+    }
+
+    pub fn grounded(&self) -> bool {
+        self.state
+            .world
+            .get::<Grounded>(self.player())
+            .is_ok_and(|grounded| grounded.0 > 0)
+    }
+
+    pub fn level(&self) -> &str {
+        &self.state.current_level
+    }
+
+    pub fn deaths(&self, level: &str) -> u32 {
+        self.state
+            .stats
+            .levels
+            .get(level)
+            .map_or(0, |stats| stats.deaths)
+    }
+
+    /// Top of a platform in pixels, given its height in grid units like in the level file.
+    pub fn surface(&self, y: f32) -> f32 {
+        self.state.levels[self.level()].size.height - y * self.state.config.player.size
+    }
+}
+
+/// Names and contents of the level files in `dir`, sorted by name.
+pub fn level_files(dir: &str) -> Vec<(String, String)> {
+    let mut files = std::fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", dir, e))
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "toml"))
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            (name, std::fs::read_to_string(&path).unwrap())
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
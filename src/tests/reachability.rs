@@ -0,0 +1,135 @@
+use ggez::graphics::Rect;
+
+use super::*;
+use crate::reachability::{analyze, can_land, can_touch, JumpModel};
+
+const DEPTH: f32 = 1000.0;
+
+const LEVEL: &str = r#"
+[size]
+width = 40
+height = 40
+
+[start]
+x = 2
+y = 2
+
+[[platform]]
+x = 0
+y = 2
+width = 10
+height = 1
+
+[[platform]]
+x = 12
+y = 4
+width = 4
+height = 1
+
+[[platform]]
+x = 20
+y = 32
+width = 4
+height = 1
+
+[[teleporter]]
+x = 13
+y = 6
+width = 2
+height = 1
+target = "second"
+
+[[teleporter]]
+x = 21
+y = 34
+width = 2
+height = 1
+target = "second"
+"#;
+
+fn model() -> JumpModel {
+    JumpModel::from_config(&Config::default()).unwrap()
+}
+
+/// A rectangle in grid units, with y pointing down like the world.
+fn cells(x: f32, y: f32, width: f32, height: f32) -> Rect {
+    let size = Config::default().player.size;
+    Rect::new(x * size, y * size, width * size, height * size)
+}
+
+fn scaled(data: &str) -> Level {
+    let mut level = toml::from_str::<Level>(data).unwrap();
+    level.scale(Config::default().player.size);
+    level
+}
+
+#[test]
+fn can_land_on_near_platforms_only() {
+    let model = model();
+    let from = cells(0.0, 10.0, 10.0, 1.0);
+
+    assert!(can_land(&model, &from, &cells(12.0, 8.0, 4.0, 1.0), DEPTH));
+    assert!(can_land(&model, &from, &cells(12.0, 14.0, 4.0, 1.0), DEPTH));
+    assert!(!can_land(
+        &model,
+        &from,
+        &cells(12.0, -10.0, 4.0, 1.0),
+        DEPTH
+    ));
+    assert!(!can_land(
+        &model,
+        &from,
+        &cells(100.0, 10.0, 4.0, 1.0),
+        DEPTH
+    ));
+}
+
+#[test]
+fn can_touch_targets_within_jump_height() {
+    let model = model();
+    let from = cells(0.0, 10.0, 10.0, 1.0);
+
+    assert!(can_touch(&model, &from, &cells(4.0, 6.0, 2.0, 1.0), DEPTH));
+    assert!(!can_touch(
+        &model,
+        &from,
+        &cells(4.0, -15.0, 2.0, 1.0),
+        DEPTH
+    ));
+}
+
+#[test]
+fn analyze_reports_unreachable_platforms_and_teleporters() {
+    let report = analyze(&scaled(LEVEL), &model());
+
+    assert!(!report.start_falls);
+    assert_eq!(report.unreachable_platforms, vec![2]);
+    assert_eq!(report.unreachable_teleporters, vec![1]);
+}
+
+#[test]
+fn analyze_reports_a_start_above_nothing() {
+    let level = LEVEL.replace("x = 2\ny = 2", "x = 30\ny = 2");
+    let report = analyze(&scaled(&level), &model());
+
+    assert!(report.start_falls);
+}
+
+#[test]
+fn jumps_without_gravity_are_rejected() {
+    let mut config = Config::default();
+    config.physics.gravity = 0.0;
+    assert!(JumpModel::from_config(&config).is_err());
+
+    config.physics.gravity = -0.2;
+    assert!(JumpModel::from_config(&config).is_err());
+}
+
+#[test]
+fn jumps_are_cut_off_if_they_never_come_down() {
+    let mut model = model();
+    model.gravity = 0.0;
+
+    assert!(model.max_rise().is_finite());
+    assert!(!model.trajectory(0.0, DEPTH).is_empty());
+}
@@ -0,0 +1,79 @@
+use super::*;
+
+const POOL: &str = r#"
+[size]
+width = 30
+height = 20
+
+[start]
+x = 4
+y = 8
+
+[[platform]]
+x = 0
+y = 2
+width = 30
+height = 1
+
+[[water]]
+x = 0
+y = 12
+width = 30
+height = 10
+"#;
+
+fn pool(air_ticks: usize) -> Harness {
+    let mut config = Config::default();
+    config.physics.water.air_ticks = air_ticks;
+    Harness::with_config(config, &[("start", POOL)])
+}
+
+#[test]
+fn player_sinks_slowly_in_water() {
+    let mut harness = pool(0);
+    let start = harness.position();
+
+    harness.run(60, Input::NONE);
+    assert!(harness.position().y > start.y);
+    assert!(harness.velocity().y > 0.0 && harness.velocity().y < 1.0);
+    assert!(!harness.grounded());
+}
+
+#[test]
+fn swim_strokes_can_be_repeated() {
+    let mut harness = pool(0);
+    let start = harness.position();
+    let size = harness.state.config.player.size;
+
+    for _ in 0..5 {
+        harness.run_script(&[(1, Input::JUMP), (9, Input::NONE)]);
+    }
+    assert!(harness.position().y < start.y - size);
+    assert!(!harness.grounded());
+}
+
+#[test]
+fn running_is_slower_in_water() {
+    let mut harness = pool(0);
+    harness.run(600, Input::NONE);
+    assert!(harness.grounded());
+
+    harness.run(200, Input::RIGHT);
+    assert!(harness.velocity().x > 0.0);
+    assert!(harness.velocity().x <= harness.state.config.physics.water.max_horizontal_velocity);
+}
+
+#[test]
+fn player_drowns_without_air() {
+    let mut harness = pool(60);
+
+    assert!(harness.run_until(100, Input::NONE, |h| h.deaths("start") == 1));
+}
+
+#[test]
+fn air_meter_can_be_disabled() {
+    let mut harness = pool(0);
+    harness.run(1000, Input::NONE);
+
+    assert_eq!(harness.deaths("start"), 0);
+}
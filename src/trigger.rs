@@ -0,0 +1,73 @@
+use crate::common::*;
+use crate::components::*;
+use crate::events::GameEvent;
+use crate::level::TriggerAction;
+
+impl GameState {
+    pub fn handle_trigger_event(&mut self, event: &GameEvent) {
+        let (trigger, entity) = match event {
+            GameEvent::TriggerEntered { trigger, entity }
+            | GameEvent::TriggerStayed { trigger, entity }
+            | GameEvent::TriggerExited { trigger, entity } => (*trigger, *entity),
+            _ => return,
+        };
+
+        let (actions, source) = match self
+            .world
+            .query_one::<(&Trigger, &BoundingBox, Option<&Requires>)>(trigger)
+        {
+            Ok(mut q) => match q.get() {
+                Some((trigger, BoundingBox(bbox), requires)) => {
+                    if !requires.is_none_or(|r| self.requirement_met(&r.0)) {
+                        return;
+                    }
+
+                    let actions = match event {
+                        GameEvent::TriggerEntered { .. } => trigger.on_enter.clone(),
+                        GameEvent::TriggerStayed { .. } => trigger.on_stay.clone(),
+                        _ => trigger.on_exit.clone(),
+                    };
+                    let source = Point2::new(bbox.x + bbox.w / 2.0, bbox.y + bbox.h / 2.0);
+                    (actions, source)
+                }
+                None => return,
+            },
+            Err(_) => return,
+        };
+
+        for action in actions {
+            self.run_trigger_action(entity, source, action);
+        }
+    }
+
+    /// Kill and damage affect any entity, everything else only reacts to the player.
+    fn run_trigger_action(&mut self, entity: Entity, source: Point2, action: TriggerAction) {
+        let is_player = self.world.get::<Player>(entity).is_ok();
+
+        match action {
+            TriggerAction::Kill => {
+                if is_player {
+                    self.events.publish(GameEvent::PlayerDied);
+                } else {
+                    let _ = self.world.despawn(entity);
+                }
+            }
+            TriggerAction::Damage { amount } => {
+                if self.world.get::<Health>(entity).is_ok() {
+                    self.damage(entity, source, amount);
+                }
+            }
+            _ if !is_player => {}
+            TriggerAction::Teleport { level } => {
+                self.events.publish(GameEvent::LevelChangeRequested(level))
+            }
+            TriggerAction::ShowText { text, ticks } => {
+                self.message = Some((text, self.tick + ticks));
+            }
+            TriggerAction::SetFlag { flag } => {
+                self.flags.insert(flag);
+            }
+            TriggerAction::PlaySound { sound } => self.play_sound(&sound),
+        }
+    }
+}
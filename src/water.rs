@@ -0,0 +1,67 @@
+use crate::common::*;
+use crate::components::*;
+use crate::events::GameEvent;
+
+impl GameState {
+    /// Entities whose center is in water swim, with gravity weakened by buoyancy. The player runs
+    /// out of air while its head is under water.
+    #[inline(always)]
+    pub fn update_water(&mut self) -> GameResult<()> {
+        let regions = self
+            .world
+            .query::<(&BoundingBox, &Water)>()
+            .iter()
+            .map(|(_id, (BoundingBox(bbox), _))| *bbox)
+            .collect::<Vec<_>>();
+        let in_water = |point: Point2| regions.iter().any(|region| region.contains(point));
+
+        let water = &self.config.physics.water;
+        let mut started = vec![];
+        let mut stopped = vec![];
+
+        for (id, (position, &BoundingBox(mut bbox), gravity, swimming, air, player)) in
+            &mut self.world.query::<(
+                &Position,
+                &BoundingBox,
+                &mut Gravity,
+                Option<&Swimming>,
+                Option<&mut Air>,
+                Option<&Player>,
+            )>()
+        {
+            bbox.translate(Vector2::new(position.0.x, position.0.y));
+            let center = Point2::new(bbox.x + bbox.w / 2.0, bbox.y + bbox.h / 2.0);
+            let head = Point2::new(center.x, bbox.top());
+
+            if in_water(center) {
+                gravity.0.y = self.config.physics.gravity - water.buoyancy;
+                if swimming.is_none() {
+                    started.push(id);
+                }
+            } else if swimming.is_some() {
+                gravity.0.y = self.config.physics.gravity;
+                stopped.push(id);
+            }
+
+            if let Some(air) = air {
+                if water.air_ticks > 0 && in_water(head) {
+                    air.0 = air.0.saturating_sub(1);
+                    if air.0 == 0 && player.is_some() {
+                        self.events.publish(GameEvent::PlayerDied);
+                    }
+                } else {
+                    air.0 = water.air_ticks;
+                }
+            }
+        }
+
+        for id in started {
+            let _ = self.world.insert_one(id, Swimming);
+        }
+        for id in stopped {
+            let _ = self.world.remove_one::<Swimming>(id);
+        }
+
+        Ok(())
+    }
+}